@@ -0,0 +1,117 @@
+use cryptopals::common::attacks::crib_drag::{self, Session};
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "commands:
+  show                         print what we know of every message
+  drag <msg> <crib>            try <crib> at every offset of message <msg>
+  lock <msg> <offset> <text>   fix keystream so message <msg> reads <text> at <offset>
+  unlock <offset> <len>        forget keystream bytes
+  save <file> | load <file>    keep or restore progress
+  help | quit";
+
+// hits scoring this well get highlighted
+const HIGHLIGHT_SCORE: f32 = 20.0;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(path) = args.get(1) else {
+        eprintln!("usage: {} <cyphertexts file> [progress file]", args[0]);
+        std::process::exit(1);
+    };
+    let mut session = Session::new(crib_drag::load_cyphertexts(path)?);
+    if let Some(progress) = args.get(2) {
+        session.load(progress)?;
+    }
+    println!("loaded {} cyphertexts\n{HELP}", session.cyphertexts.len());
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end_matches('\n');
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        if let Err(err) = run(&mut session, command, rest) {
+            println!("error: {err}");
+        }
+        if command == "quit" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn run(session: &mut Session, command: &str, rest: &str) -> Result<(), Box<dyn Error>> {
+    match command {
+        "show" => show(session),
+        "drag" => {
+            let (msg, crib) = rest.split_once(' ').ok_or("usage: drag <msg> <crib>")?;
+            drag(session, msg.parse()?, crib.as_bytes());
+        }
+        "lock" => {
+            let mut parts = rest.splitn(3, ' ');
+            let msg = parts.next().ok_or("missing message")?.parse()?;
+            let offset = parts.next().ok_or("missing offset")?.parse()?;
+            let text = parts.next().ok_or("missing text")?;
+            session.lock(msg, offset, text.as_bytes())?;
+            show(session);
+        }
+        "unlock" => {
+            let (offset, len) = rest.split_once(' ').ok_or("usage: unlock <offset> <len>")?;
+            session.unlock(offset.parse()?, len.parse()?);
+            show(session);
+        }
+        "save" => session.save(rest)?,
+        "load" => {
+            session.load(rest)?;
+            show(session);
+        }
+        "help" => println!("{HELP}"),
+        "quit" | "" => {}
+        other => println!("unknown command {other}, try help"),
+    }
+    Ok(())
+}
+
+fn show(session: &Session) {
+    for (index, plaintext) in session.plaintexts(b'_').iter().enumerate() {
+        println!("{index:3} {}", printable(plaintext));
+    }
+}
+
+fn drag(session: &Session, msg: usize, crib: &[u8]) {
+    for hit in session.drag(msg, crib) {
+        let fragments: Vec<String> = hit
+            .fragments
+            .iter()
+            .map(|f| f.as_deref().map(printable).unwrap_or_default())
+            .collect();
+        let line = format!(
+            "{:4} {:6.2} | {}",
+            hit.offset,
+            hit.score,
+            fragments.join(" | ")
+        );
+        if hit.score >= HIGHLIGHT_SCORE {
+            println!("\x1b[1;32m{line}\x1b[0m");
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+fn printable(data: &[u8]) -> String {
+    data.iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
@@ -4,7 +4,7 @@ use std::fs;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let file = fs::read("samples/s1/4.txt")?;
-    let lines = file.split(|&c| c == '\n' as u8);
+    let lines = file.split(|&c| c == b'\n');
 
     let mut max_score = 0.0;
     let mut xor_char: u8 = 0;
@@ -38,7 +38,7 @@ mod tests {
 
     #[test]
     fn test_find_best_keysize() {
-        let data: Vec<u8> = vec!['a', 'b', 'c', 'd', 'e']
+        let data: Vec<u8> = ['a', 'b', 'c', 'd', 'e']
             .iter()
             .map(|&x| x as u8)
             .cycle()
@@ -82,8 +82,8 @@ mod tests {
         let split = caesar::transpose_by_keysize(&data, 11);
 
         assert_eq!(split.len(), 11);
-        for i in 0..11 {
-            assert!(split[i].iter().all(|&x| x == i as u8));
+        for (i, column) in split.iter().enumerate() {
+            assert!(column.iter().all(|&x| x == i as u8));
         }
     }
 }
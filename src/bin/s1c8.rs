@@ -12,7 +12,7 @@ fn main() {
         let mut sum = 0;
         for i in 0..chunks.len() {
             for j in i + 1..chunks.len() {
-                if &chunks[i] == &chunks[j] {
+                if chunks[i] == chunks[j] {
                     sum += 1;
                 }
             }
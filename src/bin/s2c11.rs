@@ -14,7 +14,7 @@ fn main() {
 }
 
 fn encrypt_with_random_mode(data: &[u8]) -> (Vec<u8>, bool) {
-    let key = &aes::random_aes_key();
+    let key = aes::random_aes_key();
     let mut rnd = rand::thread_rng();
 
     let mut plaintext = vec![];
@@ -24,15 +24,14 @@ fn encrypt_with_random_mode(data: &[u8]) -> (Vec<u8>, bool) {
 
     let use_cbc = random(); // true->cbc, false->ecb
 
-    let cyphertext: Vec<u8>;
-    if use_cbc {
+    let cyphertext = if use_cbc {
         let mut iv = vec![]; // create randomized iv block
         for _ in 0..16 {
             iv.push(rnd.gen_range(0..=255));
         }
-        cyphertext = aes::cbc_encrypt(&plaintext, key, Some(&iv));
+        aes::cbc_encrypt(&plaintext, key.expose(), Some(&iv))
     } else {
-        cyphertext = aes::ecb_encrypt(&plaintext, key);
-    }
+        aes::ecb_encrypt(&plaintext, key.expose())
+    };
     (cyphertext, use_cbc)
 }
@@ -0,0 +1,15 @@
+use cryptopals::common::attacks::ecb_cut_and_paste::{self, ProfileOracle};
+
+fn main() {
+    let oracle = ProfileOracle::new();
+
+    let forged = ecb_cut_and_paste::forge_admin(|email| oracle.encrypt_profile(email))
+        .expect("couldn't forge a profile");
+    let profile = oracle
+        .decrypt_profile(&forged)
+        .expect("forged profile didn't decrypt");
+
+    for (key, value) in profile {
+        println!("{key}: {value}");
+    }
+}
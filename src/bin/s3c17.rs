@@ -0,0 +1,27 @@
+use cryptopals::common::attacks::padding_oracle::{self, PaddingOracle};
+use cryptopals::common::utils;
+use rand::seq::SliceRandom;
+
+fn main() {
+    let lines = utils::lines_from_b64_file("samples/s3/17.txt");
+    let message = lines.choose(&mut rand::thread_rng()).expect("no samples");
+    let oracle = PaddingOracle::new();
+    let (cyphertext, iv) = oracle.encrypt(message);
+
+    let decrypted = padding_oracle::decrypt(&cyphertext, &iv, |data, iv| oracle.check(data, iv))
+        .expect("padding oracle attack failed");
+    println!("{}", String::from_utf8_lossy(&decrypted.plaintext));
+    println!("used {} oracle queries", decrypted.queries);
+
+    let forged =
+        padding_oracle::encrypt(b"Play that funky music", |data, iv| oracle.check(data, iv))
+            .expect("couldn't forge a cyphertext");
+    let plaintext = oracle
+        .decrypt(&forged.cyphertext, &forged.iv)
+        .unwrap_or_default();
+    println!(
+        "forged \"{}\" with {} oracle queries",
+        String::from_utf8_lossy(&plaintext),
+        forged.queries
+    );
+}
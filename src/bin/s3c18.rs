@@ -0,0 +1,16 @@
+use cryptopals::common::{aes, utils};
+
+fn main() {
+    let cyphertext = utils::b64_to_bytes(
+        "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+    );
+    let key = "YELLOW SUBMARINE".as_bytes();
+
+    let plaintext = aes::ctr_decrypt(
+        &cyphertext,
+        key,
+        &[0; 8],
+        aes::CtrLayout::Le64Nonce64Counter,
+    );
+    println!("{}", String::from_utf8_lossy(&plaintext));
+}
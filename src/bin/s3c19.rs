@@ -0,0 +1,26 @@
+use cryptopals::common::attacks::fixed_nonce_ctr;
+use cryptopals::common::{aes, utils};
+
+fn main() {
+    let key = aes::random_aes_key();
+    let cyphertexts: Vec<Vec<u8>> = utils::lines_from_b64_file("samples/s3/19.txt")
+        .iter()
+        .map(|line| {
+            aes::ctr_encrypt(
+                line,
+                key.expose(),
+                &[0; 8],
+                aes::CtrLayout::Le64Nonce64Counter,
+            )
+        })
+        .collect();
+
+    let recovered = fixed_nonce_ctr::recover_keystream(&cyphertexts);
+    for plaintext in &recovered.plaintexts {
+        println!("{}", String::from_utf8_lossy(plaintext));
+    }
+    println!("\nconfidence per column:");
+    for (index, confidence) in recovered.confidence.iter().enumerate() {
+        println!("{index:3} {confidence:.2}");
+    }
+}
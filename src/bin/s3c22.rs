@@ -0,0 +1,18 @@
+use cryptopals::common::attacks::timestamp_seed;
+use cryptopals::common::mt19937::Mt19937;
+use rand::Rng;
+
+fn main() {
+    // simulate the waiting instead of sleeping through it
+    let mut rng = rand::thread_rng();
+    let seeded_at = timestamp_seed::unix_now() - rng.gen_range(40..=1000);
+    let output = Mt19937::new(seeded_at).next_u32();
+    let now = seeded_at + rng.gen_range(40..=1000);
+
+    let search = timestamp_seed::find_seed(output, timestamp_seed::window_before(now, 2000));
+    match search.seed {
+        Some(seed) => println!("seed {seed} (actual {seeded_at})"),
+        None => println!("no seed in the window"),
+    }
+    println!("tried {} seeds in {:?}", search.tried, search.elapsed);
+}
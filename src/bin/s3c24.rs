@@ -0,0 +1,27 @@
+use cryptopals::common::attacks::{mt19937_stream, timestamp_seed};
+use cryptopals::common::{stream, utils};
+use rand::Rng;
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let seed: u16 = rng.gen();
+    let prefix_len = rng.gen_range(5..=40);
+    let mut plaintext = utils::generate_random_string(prefix_len).into_bytes();
+    plaintext.extend([b'A'; 14]);
+    let cyphertext = stream::mt19937_encrypt(&plaintext, seed);
+
+    match mt19937_stream::recover_seed(&cyphertext, &[b'A'; 14]) {
+        Some(found) => println!("seed {found} (actual {seed})"),
+        None => println!("couldn't recover the seed"),
+    }
+
+    let token = timestamp_seed::ResetTokenOracle::new().issue();
+    let now = timestamp_seed::unix_now();
+    let search =
+        timestamp_seed::detect_time_seeded_token(&token, timestamp_seed::window_before(now, 3600));
+    match search.seed {
+        Some(issued_at) => println!("token {token} was seeded with the time {issued_at}"),
+        None => println!("token {token} doesn't look time seeded"),
+    }
+    println!("searched {} seeds in {:?}", search.tried, search.elapsed);
+}
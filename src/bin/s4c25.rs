@@ -0,0 +1,14 @@
+use cryptopals::common::attacks::ctr_edit::{self, EditOracle};
+use cryptopals::common::{aes, utils};
+
+fn main() {
+    let cyphertext = utils::bytes_from_b64_file("samples/s1/7.txt");
+    let plaintext = aes::ecb_decrypt(&cyphertext, "YELLOW SUBMARINE".as_bytes());
+
+    let oracle = EditOracle::new();
+    let encrypted = oracle.encrypt(&plaintext);
+    let recovered = ctr_edit::recover_plaintext(&encrypted, |ct, offset, newtext| {
+        oracle.edit(ct, offset, newtext)
+    });
+    println!("{}", String::from_utf8_lossy(&recovered));
+}
@@ -0,0 +1,17 @@
+use cryptopals::common::attacks::ctr_bitflip::{self, UserdataOracle};
+
+fn main() {
+    let oracle = UserdataOracle::new();
+
+    let honest = oracle.encrypt(b";admin=true;");
+    println!("quoted input is admin: {}", oracle.is_admin(&honest));
+
+    let forged = ctr_bitflip::inject(
+        |userdata| oracle.encrypt(userdata),
+        |data| oracle.is_admin(data),
+        b";admin=true;",
+    )
+    .expect("bit flipping failed");
+    println!("forged cookie is admin: {}", oracle.is_admin(&forged));
+    println!("{}", oracle.decrypt(&forged).unwrap_or_default());
+}
@@ -0,0 +1,14 @@
+use cryptopals::common::attacks::cbc_key_as_iv::{self, KeyAsIvOracle};
+use cryptopals::common::{aes, utils};
+
+fn main() {
+    let oracle = KeyAsIvOracle::new();
+    let cyphertext = oracle.encrypt(b"just a regular user");
+
+    let key = cbc_key_as_iv::recover_key(&cyphertext, |data| oracle.decrypt(data))
+        .expect("key recovery failed");
+    println!("key: {}", utils::bytes_to_hex(&key));
+
+    let plaintext = aes::cbc_decrypt(&cyphertext, &key, Some(&key));
+    println!("{}", String::from_utf8_lossy(&plaintext));
+}
@@ -0,0 +1,18 @@
+use cryptopals::common::attacks::length_extension::{self, MacOracle};
+use cryptopals::common::utils;
+
+fn main() {
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let oracle = MacOracle::new();
+    let mac = oracle.sign_sha1(message);
+
+    let forgeries = length_extension::sha1_forge(&mac, message, b";admin=true", 0..64);
+    let forged = length_extension::find_valid(forgeries, |msg, mac| oracle.verify_sha1(msg, mac))
+        .expect("no key length guess verified");
+    println!("key length: {}", forged.key_len);
+    println!(
+        "message: {}",
+        String::from_utf8_lossy(&forged.message).escape_debug()
+    );
+    println!("mac: {}", utils::bytes_to_hex(&forged.mac));
+}
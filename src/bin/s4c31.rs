@@ -0,0 +1,24 @@
+use cryptopals::common::aes;
+use cryptopals::common::attacks::hmac_timing::TimingServer;
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+// usage: s4c31 [port] [per byte delay in ms]
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let port: u16 = args.get(1).map(|p| p.parse()).transpose()?.unwrap_or(9000);
+    let delay_ms: f64 = args.get(2).map(|d| d.parse()).transpose()?.unwrap_or(50.0);
+
+    let server = TimingServer::bind(
+        ("127.0.0.1", port),
+        aes::random_aes_key().expose(),
+        Duration::from_secs_f64(delay_ms / 1000.0),
+    )?;
+    println!(
+        "listening on http://{}/test?file=...&signature=...",
+        server.local_addr()?
+    );
+    server.serve()?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use cryptopals::common::attacks::hmac_timing::{self, TimingConfig, TimingServer};
+use cryptopals::common::{aes, utils};
+use std::env;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+// usage: s4c32 [per byte delay in ms] [server address]
+// without an address a server is started in process with that delay
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let delay_ms: f64 = args.get(1).map(|d| d.parse()).transpose()?.unwrap_or(5.0);
+    let addr: SocketAddr = match args.get(2) {
+        Some(addr) => addr.parse()?,
+        None => {
+            let server = TimingServer::bind(
+                "127.0.0.1:0",
+                aes::random_aes_key().expose(),
+                Duration::from_secs_f64(delay_ms / 1000.0),
+            )?;
+            let addr = server.local_addr()?;
+            server.spawn();
+            addr
+        }
+    };
+
+    let file = "foo";
+    let recovered = hmac_timing::recover_mac(
+        20,
+        |signature| matches!(hmac_timing::query(addr, file, signature), Ok(200)),
+        &TimingConfig::default(),
+    );
+
+    println!("signature: {}", utils::bytes_to_hex(&recovered.mac));
+    println!("verified: {}", recovered.verified);
+    println!("requests: {}", recovered.requests);
+    for (index, confidence) in recovered.confidence.iter().enumerate() {
+        println!("byte {index:2}: {confidence:.1}");
+    }
+    Ok(())
+}
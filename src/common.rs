@@ -1,33 +1,117 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Decode(String),
+    Io(std::io::Error),
+    KeyLength(usize),
+    BlockLength(usize),
+    Padding(aes::PaddingError),
+    Offset(usize),
+    IndexOutOfRange(usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Decode(msg) => write!(f, "decode failed: {msg}"),
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::KeyLength(len) => write!(f, "invalid key length {len}"),
+            Error::BlockLength(len) => write!(f, "invalid block length {len}"),
+            Error::Padding(err) => write!(f, "{err}"),
+            Error::Offset(offset) => write!(f, "offset {offset} out of range"),
+            Error::IndexOutOfRange(index) => write!(f, "index {index} out of range"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Padding(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(err: base64::DecodeError) -> Self {
+        Error::Decode(format!("invalid b64 string: {err}"))
+    }
+}
+
+impl From<hex::FromHexError> for Error {
+    fn from(err: hex::FromHexError) -> Self {
+        Error::Decode(format!("invalid hex string: {err}"))
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        Error::Decode(format!("invalid utf8: {err}"))
+    }
+}
+
+impl From<aes::PaddingError> for Error {
+    fn from(err: aes::PaddingError) -> Self {
+        Error::Padding(err)
+    }
+}
+
 pub mod utils {
+    use super::Result;
     use base64::{engine::general_purpose, Engine as _};
     use hex;
-    use rand::Rng;
+    use rand::{Rng, RngCore};
     use std::fs;
     use std::path::Path;
 
     pub fn b64_to_bytes(data: &str) -> Vec<u8> {
-        general_purpose::STANDARD
-            .decode(data)
-            .expect("invalid b64 string")
+        try_b64_to_bytes(data).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_b64_to_bytes(data: &str) -> Result<Vec<u8>> {
+        Ok(general_purpose::STANDARD.decode(data)?)
     }
     pub fn bytes_to_b64(data: &[u8]) -> String {
         general_purpose::STANDARD_NO_PAD.encode(data)
     }
     pub fn bytes_from_b64_file<P: AsRef<Path>>(path: P) -> Vec<u8> {
-        let file = fs::read(path).expect("couldn't open file");
-        let file: Vec<u8> = file.iter().filter(|&c| *c != '\n' as u8).cloned().collect();
-        b64_to_bytes(&String::from_utf8(file).unwrap())
+        try_bytes_from_b64_file(path).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_bytes_from_b64_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+        let file = fs::read(path)?;
+        let file: Vec<u8> = file.iter().filter(|&c| *c != b'\n').cloned().collect();
+        try_b64_to_bytes(&String::from_utf8(file)?)
     }
     pub fn lines_from_b64_file<P: AsRef<Path>>(path: P) -> Vec<Vec<u8>> {
-        let file = String::from_utf8(fs::read(path).expect("couldn't open file")).unwrap();
-        file.split(|c| c == '\n')
-            .map(|line| b64_to_bytes(line))
-            .filter(|l| !l.is_empty())
-            .collect()
+        try_lines_from_b64_file(path).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_lines_from_b64_file<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<u8>>> {
+        let file = String::from_utf8(fs::read(path)?)?;
+        let mut lines = vec![];
+        for line in file.split('\n') {
+            let line = try_b64_to_bytes(line)?;
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        Ok(lines)
     }
 
     pub fn hex_to_bytes(data: &str) -> Vec<u8> {
-        hex::decode(data).expect("invalid hex string")
+        try_hex_to_bytes(data).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_hex_to_bytes(data: &str) -> Result<Vec<u8>> {
+        Ok(hex::decode(data)?)
     }
     pub fn bytes_to_hex(data: &[u8]) -> String {
         hex::encode(data)
@@ -50,11 +134,15 @@ pub mod utils {
             .sum::<u32>() as usize
     }
 
-    pub fn generate_random_string(length: usize) -> String {
-        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
                              abcdefghijklmnopqrstuvwxyz\
                              0123456789";
-        let mut rng = rand::thread_rng();
+
+    pub fn generate_random_string(length: usize) -> String {
+        generate_random_string_with(&mut rand::thread_rng(), length)
+    }
+    // same, but with a caller supplied generator (e.g. a seeded Mt19937)
+    pub fn generate_random_string_with<R: RngCore + ?Sized>(rng: &mut R, length: usize) -> String {
         let random_string: String = (0..length)
             .map(|_| {
                 let idx = rng.gen_range(0..CHARSET.len());
@@ -63,10 +151,81 @@ pub mod utils {
             .collect();
         random_string
     }
+    // the same characters written straight into `buf`, for keys that
+    // shouldn't leave copies behind in a String
+    pub fn fill_random_chars_with<R: RngCore + ?Sized>(rng: &mut R, buf: &mut [u8]) {
+        for byte in buf {
+            *byte = CHARSET[rng.gen_range(0..CHARSET.len())];
+        }
+    }
+
+    // touches every byte whatever the contents, only the lengths can leak
+    pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+        std::hint::black_box(diff) == 0
+    }
+
+    // picks `a` when `choice` is set, `b` otherwise, without branching on it
+    pub fn ct_select_u8(choice: bool, a: u8, b: u8) -> u8 {
+        let mask = 0u8.wrapping_sub(std::hint::black_box(choice) as u8);
+        (a & mask) | (b & !mask)
+    }
+    pub fn ct_select(choice: bool, a: &[u8], b: &[u8]) -> Vec<u8> {
+        assert_eq!(a.len(), b.len(), "ct_select needs equal lengths");
+        a.iter()
+            .zip(b)
+            .map(|(&x, &y)| ct_select_u8(choice, x, y))
+            .collect()
+    }
+
+    // key material that's wiped when dropped and never printed
+    #[derive(Clone)]
+    pub struct Secret<T: AsMut<[u8]>>(T);
+
+    impl<T: AsMut<[u8]>> Secret<T> {
+        pub fn new(value: T) -> Self {
+            Secret(value)
+        }
+
+        pub fn expose(&self) -> &T {
+            &self.0
+        }
+
+        pub fn expose_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+    }
+
+    impl<T: AsMut<[u8]>> Drop for Secret<T> {
+        fn drop(&mut self) {
+            for byte in self.0.as_mut() {
+                // volatile so the writes to memory about to be freed aren't optimised out
+                unsafe { std::ptr::write_volatile(byte, 0) };
+            }
+            std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    impl<T: AsMut<[u8]>> std::fmt::Debug for Secret<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Secret(..)")
+        }
+    }
+
+    impl<T: AsMut<[u8]> + AsRef<[u8]>> PartialEq for Secret<T> {
+        fn eq(&self, other: &Self) -> bool {
+            ct_eq(self.0.as_ref(), other.0.as_ref())
+        }
+    }
+
+    impl<T: AsMut<[u8]> + AsRef<[u8]>> Eq for Secret<T> {}
 }
 
 pub mod caesar {
-    use super::utils;
+    use super::{utils, Error, Result};
     use std::collections::HashMap;
 
     pub fn score_letter_frequency(data: &str) -> f32 {
@@ -142,6 +301,11 @@ pub mod caesar {
     }
 
     pub fn find_best_keysizes(data: &[u8]) -> Vec<usize> {
+        try_find_best_keysizes(data).unwrap_or_else(|e| panic!("{e}"))
+    }
+    // keysizes that don't give at least two chunks to compare are skipped,
+    // so short input gets fewer than five back
+    pub fn try_find_best_keysizes(data: &[u8]) -> Result<Vec<usize>> {
         let mut best_keysizes: Vec<(usize, f32)> = vec![];
 
         for keysize in 2..=40 {
@@ -156,15 +320,21 @@ pub mod caesar {
                     count += 1;
                 }
             }
+            if count == 0 {
+                continue;
+            }
             // average of hamming distances
             let dist = sum as f32 / count as f32;
             // normalize to keysize
             let dist = dist / keysize as f32;
 
             best_keysizes.push((keysize, dist));
-            best_keysizes.sort_by(|a, b| a.1.partial_cmp(&b.1).expect("can't compare!?"))
+            best_keysizes.sort_by(|a, b| a.1.total_cmp(&b.1))
+        }
+        if best_keysizes.is_empty() {
+            return Err(Error::BlockLength(data.len()));
         }
-        best_keysizes[..5].to_vec().iter().map(|x| x.0).collect()
+        Ok(best_keysizes.iter().take(5).map(|x| x.0).collect())
     }
 
     pub fn transpose_by_keysize(data: &[u8], keysize: usize) -> Vec<Vec<u8>> {
@@ -180,112 +350,239 @@ pub mod caesar {
 }
 
 pub mod aes {
-    use super::utils;
+    use super::utils::{self, Secret};
+    use super::{Error, Result};
     use aes::{
         self,
         cipher::{BlockDecryptMut, BlockEncryptMut, KeyInit},
     };
     use ecb;
+    use rand::RngCore;
+    use std::io::{Read, Write};
 
     type Aes128EcbEnc = ecb::Encryptor<aes::Aes128>;
     type Aes128EcbDec = ecb::Decryptor<aes::Aes128>;
+    type Aes192EcbEnc = ecb::Encryptor<aes::Aes192>;
+    type Aes192EcbDec = ecb::Decryptor<aes::Aes192>;
+    type Aes256EcbEnc = ecb::Encryptor<aes::Aes256>;
+    type Aes256EcbDec = ecb::Decryptor<aes::Aes256>;
+
+    fn check_key(key: &[u8]) -> Result<()> {
+        match key.len() {
+            16 | 24 | 32 => Ok(()),
+            len => Err(Error::KeyLength(len)),
+        }
+    }
+
+    fn check_lengths(data: &[u8], key: &[u8]) -> Result<()> {
+        check_key(key)?;
+        if data.len() != 16 {
+            return Err(Error::BlockLength(data.len()));
+        }
+        Ok(())
+    }
 
     pub fn ecb_encrypt_single(data: &[u8], key: &[u8]) -> Vec<u8> {
-        let data = pkcs7_pad(data, 16);
+        try_ecb_encrypt_single(data, key).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_ecb_encrypt_single(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        // a short block gets padded up to size, a full one goes in as is
+        if data.len() < 16 {
+            return encrypt_block(&pkcs7_pad(data, 16), key);
+        }
+        encrypt_block(data, key)
+    }
+    // raw block encryption, no padding added
+    fn encrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        check_lengths(data, key)?;
 
         let mut buf = [0u8; 16];
-        buf.copy_from_slice(&data);
-        Aes128EcbEnc::new(key.into()).encrypt_block_mut((&mut buf).into());
+        buf.copy_from_slice(data);
+        // key length is already checked, pick the matching cipher
+        match key.len() {
+            16 => Aes128EcbEnc::new(key.into()).encrypt_block_mut((&mut buf).into()),
+            24 => Aes192EcbEnc::new(key.into()).encrypt_block_mut((&mut buf).into()),
+            _ => Aes256EcbEnc::new(key.into()).encrypt_block_mut((&mut buf).into()),
+        }
 
-        buf.to_vec()
+        Ok(buf.to_vec())
     }
     pub fn ecb_decrypt_single(data: &[u8], key: &[u8]) -> Vec<u8> {
-        if data.len() != 16 {
-            panic!("invalid data length");
-        };
+        try_ecb_decrypt_single(data, key).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_ecb_decrypt_single(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        Ok(pkcs7_unpad(&decrypt_block(data, key)?))
+    }
+    // raw block decryption, leaves any padding in place
+    fn decrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        check_lengths(data, key)?;
 
         let mut buf = [0u8; 16];
         buf.copy_from_slice(data);
-        Aes128EcbDec::new(key.into()).decrypt_block_mut((&mut buf).into());
+        match key.len() {
+            16 => Aes128EcbDec::new(key.into()).decrypt_block_mut((&mut buf).into()),
+            24 => Aes192EcbDec::new(key.into()).decrypt_block_mut((&mut buf).into()),
+            _ => Aes256EcbDec::new(key.into()).decrypt_block_mut((&mut buf).into()),
+        }
 
-        let res = buf.to_vec();
-        pkcs7_unpad(&res)
+        Ok(buf.to_vec())
     }
     pub fn ecb_encrypt(data: &[u8], key: &[u8]) -> Vec<u8> {
+        try_ecb_encrypt(data, key).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_ecb_encrypt(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        check_key(key)?;
         let mut res = vec![];
-        for chunk in data.chunks(16) {
-            res.extend(ecb_encrypt_single(chunk, key));
+        for chunk in pkcs7_pad(data, 16).chunks(16) {
+            res.extend(encrypt_block(chunk, key)?);
         }
-        res
+        Ok(res)
     }
     pub fn ecb_decrypt(data: &[u8], key: &[u8]) -> Vec<u8> {
+        try_ecb_decrypt(data, key).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_ecb_decrypt(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        let mut res = vec![];
+        for chunk in data.chunks(16) {
+            res.extend(decrypt_block(chunk, key)?);
+        }
+        let pad_len = lenient_pad_len(&res, res.len())?;
+        res.truncate(res.len() - pad_len);
+        Ok(res)
+    }
+    // bad padding is an expected outcome here, so this one never panics
+    pub fn ecb_decrypt_strict(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
         let mut res = vec![];
         for chunk in data.chunks(16) {
-            res.extend(ecb_decrypt_single(chunk, key));
+            res.extend(decrypt_block(chunk, key)?);
         }
-        res
+        Ok(pkcs7_unpad_strict(&res, 16)?)
     }
 
     pub fn pkcs7_pad(data: &[u8], blocksize: usize) -> Vec<u8> {
-        let diff = match data.len().partial_cmp(&blocksize) {
-            Some(std::cmp::Ordering::Less) => blocksize - data.len(),
-            Some(std::cmp::Ordering::Equal) => 0,
-            Some(std::cmp::Ordering::Greater) => blocksize - (data.len() % blocksize),
-            None => panic!("comparison failed"),
-        };
+        try_pkcs7_pad(data, blocksize).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_pkcs7_pad(data: &[u8], blocksize: usize) -> Result<Vec<u8>> {
+        if blocksize == 0 || blocksize > 255 {
+            return Err(Error::BlockLength(blocksize));
+        }
+        // always pad, aligned data gets a whole block so it can be unpadded
+        let diff = blocksize - (data.len() % blocksize);
         let pad = vec![diff as u8; diff];
 
         let mut output = vec![];
         output.extend_from_slice(data);
         output.extend_from_slice(&pad);
-        output
+        Ok(output)
     }
 
     pub fn pkcs7_unpad(data: &[u8]) -> Vec<u8> {
-        let last_byte = data[data.len() - 1] as usize;
-        if last_byte >= data.len() {
+        try_pkcs7_unpad(data).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_pkcs7_unpad(data: &[u8]) -> Result<Vec<u8>> {
+        let last_byte = *data.last().ok_or(PaddingError::Empty)? as usize;
+        if last_byte > data.len() {
             // can't be padding if it's longer than data
-            return data.to_vec();
+            return Ok(data.to_vec());
         }
         let maybe_padding = &data[data.len() - last_byte..];
         if maybe_padding.len() == last_byte && maybe_padding.iter().all(|&x| x == last_byte as u8) {
-            data[0..data.len() - last_byte].to_vec()
+            Ok(data[0..data.len() - last_byte].to_vec())
         } else {
-            data.to_vec() // none, or maybe invalid padding
+            Ok(data.to_vec()) // none, or maybe invalid padding
         }
     }
 
+    #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+    pub enum PaddingError {
+        Empty,
+        ZeroPadByte,
+        PadTooLong,
+        InconsistentBytes,
+    }
+
+    impl std::fmt::Display for PaddingError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let msg = match self {
+                PaddingError::Empty => "no data to unpad",
+                PaddingError::ZeroPadByte => "pad byte is zero",
+                PaddingError::PadTooLong => "pad is longer than the block",
+                PaddingError::InconsistentBytes => "pad bytes don't match",
+            };
+            write!(f, "invalid pkcs7 padding: {msg}")
+        }
+    }
+
+    impl std::error::Error for PaddingError {}
+
+    // unlike pkcs7_unpad, anything that isn't valid padding is an error
+    pub fn pkcs7_unpad_strict(
+        data: &[u8],
+        blocksize: usize,
+    ) -> std::result::Result<Vec<u8>, PaddingError> {
+        let last_byte = *data.last().ok_or(PaddingError::Empty)?;
+        if last_byte == 0 {
+            return Err(PaddingError::ZeroPadByte);
+        }
+        let pad_len = last_byte as usize;
+        if pad_len > blocksize || pad_len > data.len() {
+            return Err(PaddingError::PadTooLong);
+        }
+        let (text, padding) = data.split_at(data.len() - pad_len);
+        if padding.iter().any(|&x| x != last_byte) {
+            return Err(PaddingError::InconsistentBytes);
+        }
+        Ok(text.to_vec())
+    }
+
     pub fn cbc_single_encrypt(block: &[u8], key: &[u8], prev_block: &[u8]) -> Vec<u8> {
+        try_cbc_single_encrypt(block, key, prev_block).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_cbc_single_encrypt(block: &[u8], key: &[u8], prev_block: &[u8]) -> Result<Vec<u8>> {
         let xored = utils::xor_bytes(block, prev_block);
-        ecb_encrypt_single(&xored, key)
+        try_ecb_encrypt_single(&xored, key)
     }
 
     pub fn cbc_single_decrypt(block: &[u8], key: &[u8], prev_block: &[u8]) -> Vec<u8> {
-        let decrypted = ecb_decrypt_single(block, key);
-        utils::xor_bytes(&decrypted, prev_block)
+        try_cbc_single_decrypt(block, key, prev_block).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_cbc_single_decrypt(block: &[u8], key: &[u8], prev_block: &[u8]) -> Result<Vec<u8>> {
+        // padding only makes sense on the final block, so leave it to the caller
+        let decrypted = decrypt_block(block, key)?;
+        Ok(utils::xor_bytes(&decrypted, prev_block))
+    }
+
+    fn check_iv(iv: &[u8]) -> Result<()> {
+        if iv.len() != 16 {
+            return Err(Error::BlockLength(iv.len()));
+        }
+        Ok(())
     }
 
     pub fn cbc_encrypt(data: &[u8], key: &[u8], iv: Option<&[u8]>) -> Vec<u8> {
+        try_cbc_encrypt(data, key, iv).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_cbc_encrypt(data: &[u8], key: &[u8], iv: Option<&[u8]>) -> Result<Vec<u8>> {
         let iv: &[u8] = iv.unwrap_or(&[0u8; 16]);
+        check_iv(iv)?;
         let data = pkcs7_pad(data, 16);
         assert_eq!(&data.len() % 16, 0);
-        let mut blocks: Vec<&[u8]> = vec![];
-        blocks.push(iv);
-        for chunk in data.chunks(16) {
-            blocks.push(chunk);
-        }
         let mut output: Vec<u8> = vec![];
-        for window in blocks.windows(2) {
-            if let [prev, curr] = window {
-                let res = cbc_single_encrypt(curr, key, prev);
-                output.extend_from_slice(&res);
-            }
+        let mut prev = iv.to_vec();
+        for chunk in data.chunks(16) {
+            // chain off the previous cyphertext block, not the plaintext
+            prev = try_cbc_single_encrypt(chunk, key, &prev)?;
+            output.extend_from_slice(&prev);
         }
-        output
+        Ok(output)
     }
 
     pub fn cbc_decrypt(data: &[u8], key: &[u8], iv: Option<&[u8]>) -> Vec<u8> {
+        try_cbc_decrypt(data, key, iv).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_cbc_decrypt(data: &[u8], key: &[u8], iv: Option<&[u8]>) -> Result<Vec<u8>> {
         let iv: &[u8] = iv.unwrap_or(&[0u8; 16]);
+        check_iv(iv)?;
         let mut blocks: Vec<&[u8]> = vec![];
         blocks.push(iv);
         for chunk in data.chunks(16) {
@@ -294,11 +591,393 @@ pub mod aes {
         let mut output: Vec<u8> = vec![];
         for window in blocks.windows(2) {
             if let [prev, curr] = window {
-                let res = cbc_single_decrypt(curr, key, prev);
+                let res = try_cbc_single_decrypt(curr, key, prev)?;
                 output.extend_from_slice(&res);
             }
         }
-        pkcs7_unpad(&output)
+        let pad_len = lenient_pad_len(&output, output.len())?;
+        output.truncate(output.len() - pad_len);
+        Ok(output)
+    }
+
+    // same rules as pkcs7_unpad, but padding never reaches past the last block.
+    // `tail` is the end of the plaintext, `total_len` the length of all of it
+    fn lenient_pad_len(tail: &[u8], total_len: usize) -> Result<usize> {
+        let last_byte = *tail.last().ok_or(PaddingError::Empty)? as usize;
+        if last_byte > total_len || last_byte > 16 {
+            return Ok(0);
+        }
+        if tail[tail.len() - last_byte..]
+            .iter()
+            .all(|&x| x == last_byte as u8)
+        {
+            Ok(last_byte)
+        } else {
+            Ok(0)
+        }
+    }
+
+    pub fn cbc_decrypt_strict(data: &[u8], key: &[u8], iv: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut prev: &[u8] = iv.unwrap_or(&[0u8; 16]);
+        check_iv(prev)?;
+        let mut output: Vec<u8> = vec![];
+        for chunk in data.chunks(16) {
+            output.extend(utils::xor_bytes(&decrypt_block(chunk, key)?, prev));
+            prev = chunk;
+        }
+        Ok(pkcs7_unpad_strict(&output, 16)?)
+    }
+
+    pub struct CbcEncryptor {
+        key: Secret<Vec<u8>>,
+        prev: Vec<u8>,
+        buf: Vec<u8>,
+    }
+
+    impl CbcEncryptor {
+        pub fn new(key: &[u8], iv: Option<&[u8]>) -> Self {
+            Self::try_new(key, iv).unwrap_or_else(|e| panic!("{e}"))
+        }
+        pub fn try_new(key: &[u8], iv: Option<&[u8]>) -> Result<Self> {
+            let iv: &[u8] = iv.unwrap_or(&[0u8; 16]);
+            check_key(key)?;
+            check_iv(iv)?;
+            Ok(CbcEncryptor {
+                key: Secret::new(key.to_vec()),
+                prev: iv.to_vec(),
+                buf: vec![],
+            })
+        }
+
+        pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+            self.buf.extend_from_slice(data);
+            let full_len = self.buf.len() / 16 * 16;
+            let mut output = vec![];
+            for chunk in self.buf[..full_len].chunks(16) {
+                self.prev = cbc_single_encrypt(chunk, self.key.expose(), &self.prev);
+                output.extend_from_slice(&self.prev);
+            }
+            self.buf.drain(..full_len);
+            output
+        }
+
+        pub fn finalize(mut self) -> Vec<u8> {
+            let padded = pkcs7_pad(&self.buf, 16);
+            self.buf.clear();
+            self.update(&padded)
+        }
+    }
+
+    pub struct CbcDecryptor {
+        key: Secret<Vec<u8>>,
+        prev: Vec<u8>,
+        buf: Vec<u8>,
+        total_len: usize,
+    }
+
+    impl CbcDecryptor {
+        pub fn new(key: &[u8], iv: Option<&[u8]>) -> Self {
+            Self::try_new(key, iv).unwrap_or_else(|e| panic!("{e}"))
+        }
+        pub fn try_new(key: &[u8], iv: Option<&[u8]>) -> Result<Self> {
+            let iv: &[u8] = iv.unwrap_or(&[0u8; 16]);
+            check_key(key)?;
+            check_iv(iv)?;
+            Ok(CbcDecryptor {
+                key: Secret::new(key.to_vec()),
+                prev: iv.to_vec(),
+                buf: vec![],
+                total_len: 0,
+            })
+        }
+
+        pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+            self.buf.extend_from_slice(data);
+            // always hold back the last block, it may carry padding
+            let ready_len = self.buf.len().saturating_sub(1) / 16 * 16;
+            let mut output = vec![];
+            for chunk in self.buf[..ready_len].chunks(16) {
+                output.extend(cbc_single_decrypt(chunk, self.key.expose(), &self.prev));
+                self.prev = chunk.to_vec();
+            }
+            self.buf.drain(..ready_len);
+            self.total_len += output.len();
+            output
+        }
+
+        fn last_block(&self) -> Result<Vec<u8>> {
+            if self.buf.is_empty() {
+                return Err(PaddingError::Empty.into());
+            }
+            try_cbc_single_decrypt(&self.buf, self.key.expose(), &self.prev)
+        }
+
+        pub fn finalize(self) -> Vec<u8> {
+            self.try_finalize().unwrap_or_else(|e| panic!("{e}"))
+        }
+        pub fn try_finalize(self) -> Result<Vec<u8>> {
+            let mut block = self.last_block()?;
+            let pad_len = lenient_pad_len(&block, self.total_len + block.len())?;
+            block.truncate(block.len() - pad_len);
+            Ok(block)
+        }
+        pub fn try_finalize_strict(self) -> Result<Vec<u8>> {
+            Ok(pkcs7_unpad_strict(&self.last_block()?, 16)?)
+        }
+    }
+
+    pub trait CbcStream {
+        fn update(&mut self, data: &[u8]) -> Vec<u8>;
+        fn try_finalize(self) -> Result<Vec<u8>>;
+    }
+
+    impl CbcStream for CbcEncryptor {
+        fn update(&mut self, data: &[u8]) -> Vec<u8> {
+            CbcEncryptor::update(self, data)
+        }
+        fn try_finalize(self) -> Result<Vec<u8>> {
+            Ok(self.finalize())
+        }
+    }
+
+    impl CbcStream for CbcDecryptor {
+        fn update(&mut self, data: &[u8]) -> Vec<u8> {
+            CbcDecryptor::update(self, data)
+        }
+        fn try_finalize(self) -> Result<Vec<u8>> {
+            CbcDecryptor::try_finalize(self)
+        }
+    }
+
+    fn to_io_error(err: Error) -> std::io::Error {
+        match err {
+            Error::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::InvalidData, err),
+        }
+    }
+
+    // pushes everything written through the stream into `inner`,
+    // call `finish` to flush the final block
+    pub struct CbcWriter<S: CbcStream, W: Write> {
+        stream: S,
+        inner: W,
+    }
+
+    impl<S: CbcStream, W: Write> CbcWriter<S, W> {
+        pub fn new(stream: S, inner: W) -> Self {
+            CbcWriter { stream, inner }
+        }
+
+        pub fn finish(mut self) -> std::io::Result<W> {
+            let last = self.stream.try_finalize().map_err(to_io_error)?;
+            self.inner.write_all(&last)?;
+            self.inner.flush()?;
+            Ok(self.inner)
+        }
+    }
+
+    impl<S: CbcStream, W: Write> Write for CbcWriter<S, W> {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            let output = self.stream.update(data);
+            self.inner.write_all(&output)?;
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    // reads from `inner` and hands back the stream's output, finalizing at EOF
+    pub struct CbcReader<S: CbcStream, R: Read> {
+        stream: Option<S>,
+        inner: R,
+        pending: Vec<u8>,
+    }
+
+    impl<S: CbcStream, R: Read> CbcReader<S, R> {
+        pub fn new(stream: S, inner: R) -> Self {
+            CbcReader {
+                stream: Some(stream),
+                inner,
+                pending: vec![],
+            }
+        }
+    }
+
+    impl<S: CbcStream, R: Read> Read for CbcReader<S, R> {
+        fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+            let mut chunk = [0u8; 4096];
+            while self.pending.is_empty() {
+                let Some(stream) = self.stream.as_mut() else {
+                    return Ok(0);
+                };
+                let read = self.inner.read(&mut chunk)?;
+                if read == 0 {
+                    let stream = self.stream.take().expect("stream checked above");
+                    self.pending = stream.try_finalize().map_err(to_io_error)?;
+                } else {
+                    self.pending = stream.update(&chunk[..read]);
+                }
+            }
+            let len = out.len().min(self.pending.len());
+            out[..len].copy_from_slice(&self.pending[..len]);
+            self.pending.drain(..len);
+            Ok(len)
+        }
+    }
+
+    #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+    pub enum CtrLayout {
+        // 64-bit little-endian nonce, then 64-bit little-endian counter (cryptopals)
+        Le64Nonce64Counter,
+        // 96-bit big-endian nonce, then 32-bit big-endian counter
+        Be96Nonce32Counter,
+    }
+
+    impl CtrLayout {
+        pub fn nonce_len(&self) -> usize {
+            match self {
+                CtrLayout::Le64Nonce64Counter => 8,
+                CtrLayout::Be96Nonce32Counter => 12,
+            }
+        }
+
+        fn counter_block(&self, nonce: &[u8], counter: u64) -> Vec<u8> {
+            let mut block = nonce.to_vec();
+            match self {
+                CtrLayout::Le64Nonce64Counter => block.extend(counter.to_le_bytes()),
+                // 32-bit counter wraps around, same as every other implementation
+                CtrLayout::Be96Nonce32Counter => block.extend((counter as u32).to_be_bytes()),
+            }
+            block
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct CtrKeystream {
+        key: Secret<Vec<u8>>,
+        nonce: Vec<u8>,
+        layout: CtrLayout,
+        counter: u64,
+        block: Vec<u8>,
+        pos: usize,
+    }
+
+    impl CtrKeystream {
+        pub fn new(key: &[u8], nonce: &[u8], layout: CtrLayout) -> Self {
+            Self::try_new(key, nonce, layout).unwrap_or_else(|e| panic!("{e}"))
+        }
+        pub fn try_new(key: &[u8], nonce: &[u8], layout: CtrLayout) -> Result<Self> {
+            check_key(key)?;
+            if nonce.len() != layout.nonce_len() {
+                return Err(Error::BlockLength(nonce.len()));
+            }
+            let mut keystream = CtrKeystream {
+                key: Secret::new(key.to_vec()),
+                nonce: nonce.to_vec(),
+                layout,
+                counter: 0,
+                block: vec![],
+                pos: 0,
+            };
+            keystream.seek(0);
+            Ok(keystream)
+        }
+
+        // jump to an arbitrary byte offset in the keystream
+        pub fn seek(&mut self, offset: u64) {
+            self.counter = offset / 16;
+            self.pos = (offset % 16) as usize;
+            self.refill();
+        }
+
+        pub fn position(&self) -> u64 {
+            self.counter * 16 + self.pos as u64
+        }
+
+        fn refill(&mut self) {
+            let counter_block = self.layout.counter_block(&self.nonce, self.counter);
+            self.block = ecb_encrypt_single(&counter_block, self.key.expose());
+        }
+
+        pub fn apply(&mut self, data: &[u8]) -> Vec<u8> {
+            data.iter().zip(self.by_ref()).map(|(x, k)| x ^ k).collect()
+        }
+    }
+
+    impl Iterator for CtrKeystream {
+        type Item = u8;
+
+        fn next(&mut self) -> Option<u8> {
+            if self.pos == 16 {
+                self.counter = self.counter.wrapping_add(1);
+                self.pos = 0;
+                self.refill();
+            }
+            let byte = self.block[self.pos];
+            self.pos += 1;
+            Some(byte)
+        }
+    }
+
+    pub fn ctr_encrypt(data: &[u8], key: &[u8], nonce: &[u8], layout: CtrLayout) -> Vec<u8> {
+        try_ctr_encrypt(data, key, nonce, layout).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_ctr_encrypt(
+        data: &[u8],
+        key: &[u8],
+        nonce: &[u8],
+        layout: CtrLayout,
+    ) -> Result<Vec<u8>> {
+        Ok(CtrKeystream::try_new(key, nonce, layout)?.apply(data))
+    }
+    // CTR is symmetric, decrypting is the same operation
+    pub fn ctr_decrypt(data: &[u8], key: &[u8], nonce: &[u8], layout: CtrLayout) -> Vec<u8> {
+        ctr_encrypt(data, key, nonce, layout)
+    }
+    pub fn try_ctr_decrypt(
+        data: &[u8],
+        key: &[u8],
+        nonce: &[u8],
+        layout: CtrLayout,
+    ) -> Result<Vec<u8>> {
+        try_ctr_encrypt(data, key, nonce, layout)
+    }
+
+    // re-encrypts `newtext` over the plaintext at `offset`, the rest of the
+    // cyphertext is left alone. Writing past the end grows it
+    pub fn ctr_edit(
+        cyphertext: &[u8],
+        key: &[u8],
+        nonce: &[u8],
+        layout: CtrLayout,
+        offset: usize,
+        newtext: &[u8],
+    ) -> Vec<u8> {
+        try_ctr_edit(cyphertext, key, nonce, layout, offset, newtext)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_ctr_edit(
+        cyphertext: &[u8],
+        key: &[u8],
+        nonce: &[u8],
+        layout: CtrLayout,
+        offset: usize,
+        newtext: &[u8],
+    ) -> Result<Vec<u8>> {
+        if offset > cyphertext.len() {
+            return Err(Error::Offset(offset));
+        }
+        let mut keystream = CtrKeystream::try_new(key, nonce, layout)?;
+        keystream.seek(offset as u64);
+
+        let mut edited = cyphertext[..offset].to_vec();
+        edited.extend(keystream.apply(newtext));
+        if let Some(rest) = cyphertext.get(offset + newtext.len()..) {
+            edited.extend_from_slice(rest);
+        }
+        Ok(edited)
     }
 
     #[derive(PartialEq, Eq, Debug)]
@@ -308,6 +987,12 @@ pub mod aes {
     }
 
     pub fn encryption_oracle(data: &[u8], keysize: usize) -> Mode {
+        try_encryption_oracle(data, keysize).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_encryption_oracle(data: &[u8], keysize: usize) -> Result<Mode> {
+        if keysize == 0 {
+            return Err(Error::BlockLength(keysize));
+        }
         let num_chunks = data.len() / keysize;
         let chunks: Vec<Vec<u8>> = data
             .chunks(keysize)
@@ -318,27 +1003,134 @@ pub mod aes {
         let mut sum = 0;
         for i in 0..chunks.len() {
             for j in i + 1..chunks.len() {
-                if &chunks[i] == &chunks[j] {
+                if chunks[i] == chunks[j] {
                     sum += 1;
                 }
             }
         }
 
         if sum > 0 {
-            Mode::Ecb
+            Ok(Mode::Ecb)
         } else {
-            Mode::Cbc
+            Ok(Mode::Cbc)
+        }
+    }
+
+    pub fn random_aes_key() -> Secret<Vec<u8>> {
+        random_aes_key_with(&mut rand::thread_rng())
+    }
+    pub fn random_aes_key_with<R: RngCore + ?Sized>(rng: &mut R) -> Secret<Vec<u8>> {
+        let mut key = Secret::new(vec![0u8; 16]);
+        utils::fill_random_chars_with(rng, key.expose_mut());
+        key
+    }
+    pub fn random_aes_key_of_size(size: usize) -> Secret<Vec<u8>> {
+        try_random_aes_key_of_size(size).unwrap_or_else(|e| panic!("{e}"))
+    }
+    pub fn try_random_aes_key_of_size(size: usize) -> Result<Secret<Vec<u8>>> {
+        let mut key = Secret::new(vec![0u8; size]);
+        check_key(key.expose())?;
+        utils::fill_random_chars_with(&mut rand::thread_rng(), key.expose_mut());
+        Ok(key)
+    }
+}
+
+pub mod stream {
+    use super::aes::CtrKeystream;
+    use super::mt19937::Mt19937;
+
+    // a seekable keystream xored over the data, encrypting and decrypting
+    // are the same operation
+    pub trait StreamCipher {
+        fn seek(&mut self, offset: u64);
+        fn apply(&mut self, data: &[u8]) -> Vec<u8>;
+
+        fn encrypt(&mut self, data: &[u8]) -> Vec<u8> {
+            self.seek(0);
+            self.apply(data)
+        }
+        fn decrypt(&mut self, data: &[u8]) -> Vec<u8> {
+            self.encrypt(data)
+        }
+    }
+
+    impl StreamCipher for CtrKeystream {
+        fn seek(&mut self, offset: u64) {
+            CtrKeystream::seek(self, offset)
+        }
+        fn apply(&mut self, data: &[u8]) -> Vec<u8> {
+            CtrKeystream::apply(self, data)
+        }
+    }
+
+    // keystream is the generator output as little-endian bytes, same as
+    // what fill_bytes gives
+    #[derive(Clone)]
+    pub struct Mt19937Cipher {
+        seed: u16,
+        rng: Mt19937,
+        word: [u8; 4],
+        pos: usize,
+    }
+
+    impl Mt19937Cipher {
+        pub fn new(seed: u16) -> Self {
+            Mt19937Cipher {
+                seed,
+                rng: Mt19937::new(seed as u32),
+                word: [0; 4],
+                pos: 4,
+            }
+        }
+    }
+
+    impl Iterator for Mt19937Cipher {
+        type Item = u8;
+
+        fn next(&mut self) -> Option<u8> {
+            if self.pos == 4 {
+                self.word = self.rng.next_u32().to_le_bytes();
+                self.pos = 0;
+            }
+            self.pos += 1;
+            Some(self.word[self.pos - 1])
+        }
+    }
+
+    impl StreamCipher for Mt19937Cipher {
+        // no random access into the generator, start over and skip ahead
+        fn seek(&mut self, offset: u64) {
+            *self = Mt19937Cipher::new(self.seed);
+            for _ in 0..offset / 4 {
+                self.rng.next_u32();
+            }
+            self.by_ref().take((offset % 4) as usize).for_each(drop);
+        }
+        fn apply(&mut self, data: &[u8]) -> Vec<u8> {
+            data.iter().zip(self.by_ref()).map(|(x, k)| x ^ k).collect()
         }
     }
 
-    pub fn random_aes_key() -> Vec<u8> {
-        utils::generate_random_string(16).as_bytes().to_vec()
+    pub fn mt19937_encrypt(data: &[u8], seed: u16) -> Vec<u8> {
+        Mt19937Cipher::new(seed).encrypt(data)
+    }
+    pub fn mt19937_decrypt(data: &[u8], seed: u16) -> Vec<u8> {
+        mt19937_encrypt(data, seed)
     }
 }
 
+pub mod attacks;
+pub mod cookie;
+pub mod mac;
+pub mod md;
+pub mod md4;
+pub mod mt19937;
+pub mod sha1;
+
 #[cfg(test)]
 mod utils_tests {
     use super::utils::*;
+    use super::Error;
 
     #[test]
     fn test_hex_to_bytes() {
@@ -358,6 +1150,50 @@ mod utils_tests {
 
         assert_eq!(hamming_dist(string1.as_bytes(), string2.as_bytes()), 37);
     }
+
+    #[test]
+    fn test_try_decode_errors() {
+        assert!(matches!(try_hex_to_bytes("49276zz"), Err(Error::Decode(_))));
+        assert!(matches!(try_b64_to_bytes("SSdt!"), Err(Error::Decode(_))));
+        assert!(matches!(
+            try_bytes_from_b64_file("samples/does/not/exist.txt"),
+            Err(Error::Io(_))
+        ));
+        assert_eq!(try_b64_to_bytes("SSdt").unwrap(), b"I'm");
+    }
+
+    #[test]
+    fn test_ct_eq_and_select() {
+        assert!(ct_eq(b"YELLOW SUBMARINE", b"YELLOW SUBMARINE"));
+        assert!(!ct_eq(b"YELLOW SUBMARINE", b"YELLOW SUBMARINF"));
+        assert!(!ct_eq(b"YELLOW", b"YELLOW SUBMARINE"));
+        assert!(ct_eq(b"", b""));
+
+        assert_eq!(ct_select_u8(true, 0xaa, 0x55), 0xaa);
+        assert_eq!(ct_select_u8(false, 0xaa, 0x55), 0x55);
+        assert_eq!(ct_select(true, b"abc", b"xyz"), b"abc");
+        assert_eq!(ct_select(false, b"abc", b"xyz"), b"xyz");
+    }
+
+    #[test]
+    fn test_secret() {
+        let secret = Secret::new(b"YELLOW SUBMARINE".to_vec());
+        assert_eq!(secret.expose(), b"YELLOW SUBMARINE");
+        assert_eq!(format!("{secret:?}"), "Secret(..)");
+        assert_eq!(secret.clone(), secret);
+        assert_ne!(Secret::new(b"YELLOW SUBMARINF".to_vec()), secret);
+    }
+
+    #[test]
+    fn test_fill_random_chars_matches_string() {
+        use crate::common::mt19937::Mt19937;
+        let mut buf = [0u8; 24];
+        fill_random_chars_with(&mut Mt19937::new(25), &mut buf);
+        assert_eq!(
+            &buf[..],
+            generate_random_string_with(&mut Mt19937::new(25), 24).as_bytes()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -375,11 +1211,22 @@ mod caesar_tests {
 
         assert!(english_score > gibberish_score);
     }
+
+    #[test]
+    fn test_try_find_best_keysizes() {
+        assert!(matches!(
+            try_find_best_keysizes(b"hi"),
+            Err(super::Error::BlockLength(2))
+        ));
+        assert_eq!(try_find_best_keysizes(b"hey").unwrap(), vec![2]);
+        assert_eq!(try_find_best_keysizes(&[7; 12]).unwrap().len(), 5);
+    }
 }
 
 #[cfg(test)]
 mod aes_tests {
     use super::aes::*;
+    use super::Error;
 
     #[test]
     fn test_pkcs7_pad_shorter() {
@@ -395,8 +1242,8 @@ mod aes_tests {
         let data = "hello world".as_bytes();
 
         let pad = pkcs7_pad(data, 11);
-        assert_eq!(pad.len(), 11);
-        assert_eq!(pad[pad.len() - 1], 'd' as u8); // not padded
+        assert_eq!(pad.len(), 22);
+        assert_eq!(&pad[11..], &[11u8; 11]); // a whole block of padding
     }
     #[test]
     fn test_pkcs7_pad_longer() {
@@ -423,6 +1270,34 @@ mod aes_tests {
         assert_eq!(pkcs7_unpad(data), "hello world".as_bytes());
     }
 
+    #[test]
+    fn test_pkcs7_unpad_strict() {
+        let mut data = "YELLOW SUBMARINE".as_bytes().to_vec();
+        data.extend_from_slice(&[4, 4, 4, 4]);
+
+        assert_eq!(
+            pkcs7_unpad_strict(&data, 16).unwrap(),
+            "YELLOW SUBMARINE".as_bytes()
+        );
+        assert_eq!(pkcs7_unpad_strict(&[16; 16], 16).unwrap(), vec![]);
+    }
+    #[test]
+    fn test_pkcs7_unpad_strict_errors() {
+        assert_eq!(pkcs7_unpad_strict(&[], 16), Err(PaddingError::Empty));
+        assert_eq!(
+            pkcs7_unpad_strict(b"ICE ICE BABY\x00", 16),
+            Err(PaddingError::ZeroPadByte)
+        );
+        assert_eq!(
+            pkcs7_unpad_strict(&[17; 32], 16),
+            Err(PaddingError::PadTooLong)
+        );
+        assert_eq!(
+            pkcs7_unpad_strict(b"ICE ICE BABY\x01\x02\x03\x04", 16),
+            Err(PaddingError::InconsistentBytes)
+        );
+    }
+
     #[test]
     fn test_ecb_encrypt_decrypt() {
         let data = vec![123; 16];
@@ -455,4 +1330,367 @@ mod aes_tests {
 
         assert_eq!(data, decrypted);
     }
+
+    #[test]
+    fn test_cbc_encrypt_decrypt_multiblock() {
+        let data = "hello world hello world hello world".as_bytes();
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let iv = vec![7u8; 16];
+        let encrypted = cbc_encrypt(data, key, Some(&iv));
+        let decrypted = cbc_decrypt(&encrypted, key, Some(&iv));
+
+        assert_eq!(encrypted.len(), 48);
+        assert_eq!(data, decrypted);
+    }
+
+    #[test]
+    fn test_cbc_decrypt_strict() {
+        let data = "hello world hello world hello world".as_bytes();
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let iv = vec![7u8; 16];
+        let mut encrypted = cbc_encrypt(data, key, Some(&iv));
+
+        assert_eq!(
+            cbc_decrypt_strict(&encrypted, key, Some(&iv)).unwrap(),
+            data
+        );
+
+        // flipping the last byte of the previous block corrupts the padding
+        let len = encrypted.len();
+        encrypted[len - 17] ^= 0x7f;
+        assert!(cbc_decrypt_strict(&encrypted, key, Some(&iv)).is_err());
+    }
+
+    #[test]
+    fn test_ecb_decrypt_strict() {
+        let data = "hello world hello world".as_bytes();
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let encrypted = ecb_encrypt(data, key);
+
+        assert_eq!(ecb_decrypt_strict(&encrypted, key).unwrap(), data);
+        assert!(matches!(
+            ecb_decrypt_strict(&encrypted[..16], key),
+            Err(Error::Padding(PaddingError::PadTooLong))
+        ));
+    }
+
+    #[test]
+    fn test_strict_bad_lengths_are_errors() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let encrypted = ecb_encrypt(b"hello world", key);
+        assert!(matches!(
+            ecb_decrypt_strict(&encrypted, &key[..15]),
+            Err(Error::KeyLength(15))
+        ));
+        assert!(matches!(
+            ecb_decrypt_strict(&encrypted[..15], key),
+            Err(Error::BlockLength(15))
+        ));
+        assert!(matches!(
+            cbc_decrypt_strict(&encrypted, &key[..15], None),
+            Err(Error::KeyLength(15))
+        ));
+        assert!(matches!(
+            cbc_decrypt_strict(&encrypted[..15], key, None),
+            Err(Error::BlockLength(15))
+        ));
+    }
+
+    #[test]
+    fn test_strict_round_trip_aligned() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let iv = vec![7u8; 16];
+        let data = [0x42u8; 48];
+        for len in [0, 16, 32, 48] {
+            let data = &data[..len];
+            let encrypted = ecb_encrypt(data, key);
+            assert_eq!(encrypted.len(), len + 16);
+            assert_eq!(ecb_decrypt_strict(&encrypted, key).unwrap(), data);
+            assert_eq!(ecb_decrypt(&encrypted, key), data);
+
+            let encrypted = cbc_encrypt(data, key, Some(&iv));
+            assert_eq!(encrypted.len(), len + 16);
+            assert_eq!(
+                cbc_decrypt_strict(&encrypted, key, Some(&iv)).unwrap(),
+                data
+            );
+            assert_eq!(cbc_decrypt(&encrypted, key, Some(&iv)), data);
+        }
+    }
+
+    #[test]
+    fn test_try_aes_errors() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        assert!(matches!(
+            try_ecb_encrypt(b"hello", b"short key"),
+            Err(Error::KeyLength(9))
+        ));
+        assert!(matches!(
+            try_ecb_decrypt(&[0; 20], key),
+            Err(Error::BlockLength(4))
+        ));
+        assert!(matches!(
+            try_cbc_encrypt(b"hello", key, Some(&[0; 8])),
+            Err(Error::BlockLength(8))
+        ));
+        assert!(matches!(
+            try_pkcs7_pad(b"hello", 0),
+            Err(Error::BlockLength(0))
+        ));
+        assert!(matches!(
+            cbc_decrypt_strict(&ecb_encrypt(&[0; 16], key), key, None),
+            Err(Error::Padding(_))
+        ));
+    }
+
+    #[test]
+    fn test_ctr_decrypt_cryptopals() {
+        let data = super::utils::b64_to_bytes(
+            "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+        );
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let decrypted = ctr_decrypt(&data, key, &[0; 8], CtrLayout::Le64Nonce64Counter);
+
+        assert_eq!(
+            String::from_utf8(decrypted).unwrap(),
+            "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby "
+        );
+    }
+
+    #[test]
+    fn test_ctr_be96_nist_vector() {
+        // NIST SP 800-38A F.5.1, initial counter block f0f1...fcfdfeff
+        let key = super::utils::hex_to_bytes("2b7e151628aed2a6abf7158809cf4f3c");
+        let nonce = super::utils::hex_to_bytes("f0f1f2f3f4f5f6f7f8f9fafb");
+        let plaintext = super::utils::hex_to_bytes(
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51",
+        );
+        let mut keystream = CtrKeystream::new(&key, &nonce, CtrLayout::Be96Nonce32Counter);
+        keystream.seek(0xfcfdfeff * 16);
+
+        assert_eq!(
+            super::utils::bytes_to_hex(&keystream.apply(&plaintext)),
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff"
+        );
+    }
+
+    #[test]
+    fn test_ctr_seek() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let data = vec![0u8; 100];
+        let full = ctr_encrypt(&data, key, &[3; 8], CtrLayout::Le64Nonce64Counter);
+
+        let mut keystream = CtrKeystream::new(key, &[3; 8], CtrLayout::Le64Nonce64Counter);
+        for offset in [0, 1, 15, 16, 17, 47, 99] {
+            keystream.seek(offset);
+            assert_eq!(keystream.position(), offset);
+            assert_eq!(
+                keystream.apply(&data[offset as usize..]),
+                full[offset as usize..]
+            );
+        }
+    }
+
+    #[test]
+    fn test_ctr_edit() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let layout = CtrLayout::Le64Nonce64Counter;
+        let cyphertext = ctr_encrypt(b"the quick brown fox jumps", key, &[1; 8], layout);
+
+        let edited = ctr_edit(&cyphertext, key, &[1; 8], layout, 10, b"green");
+        assert_eq!(edited[..10], cyphertext[..10]);
+        assert_eq!(edited[15..], cyphertext[15..]);
+        assert_eq!(
+            ctr_decrypt(&edited, key, &[1; 8], layout),
+            b"the quick green fox jumps"
+        );
+
+        let grown = ctr_edit(&cyphertext, key, &[1; 8], layout, 20, b"leaps high");
+        assert_eq!(
+            ctr_decrypt(&grown, key, &[1; 8], layout),
+            b"the quick brown fox leaps high"
+        );
+        assert!(matches!(
+            try_ctr_edit(&cyphertext, key, &[1; 8], layout, 26, b"x"),
+            Err(Error::Offset(26))
+        ));
+    }
+
+    #[test]
+    fn test_ctr_bad_nonce() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        assert!(matches!(
+            try_ctr_encrypt(b"hello", key, &[0; 8], CtrLayout::Be96Nonce32Counter),
+            Err(Error::BlockLength(8))
+        ));
+    }
+
+    #[test]
+    fn test_aes192_aes256_vectors() {
+        // FIPS 197 appendix C.2 and C.3
+        let plaintext = super::utils::hex_to_bytes("00112233445566778899aabbccddeeff");
+        let vectors = [
+            (
+                "000102030405060708090a0b0c0d0e0f1011121314151617",
+                "dda97ca4864cdfe06eaf70a0ec0d7191",
+            ),
+            (
+                "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                "8ea2b7ca516745bfeafc49904b496089",
+            ),
+        ];
+        for (key, expected) in vectors {
+            let key = super::utils::hex_to_bytes(key);
+            let encrypted = ecb_encrypt_single(&plaintext, &key);
+            assert_eq!(super::utils::bytes_to_hex(&encrypted), expected);
+            assert_eq!(
+                ecb_decrypt_strict(&ecb_encrypt(&[4; 12], &key), &key).unwrap(),
+                vec![4; 12]
+            );
+        }
+    }
+
+    #[test]
+    fn test_all_key_sizes_all_modes() {
+        let data = "hello world hello world hello world".as_bytes();
+        let iv = vec![9u8; 16];
+        for size in [16, 24, 32] {
+            let secret = random_aes_key_of_size(size);
+            let key = secret.expose();
+            assert_eq!(key.len(), size);
+
+            let encrypted = cbc_encrypt(data, key, Some(&iv));
+            assert_eq!(
+                cbc_decrypt_strict(&encrypted, key, Some(&iv)).unwrap(),
+                data
+            );
+
+            let encrypted = ctr_encrypt(data, key, &[1; 8], CtrLayout::Le64Nonce64Counter);
+            assert_eq!(
+                ctr_decrypt(&encrypted, key, &[1; 8], CtrLayout::Le64Nonce64Counter),
+                data
+            );
+        }
+        assert!(matches!(
+            try_random_aes_key_of_size(20),
+            Err(Error::KeyLength(20))
+        ));
+        assert!(matches!(
+            try_cbc_encrypt(data, &[0; 17], None),
+            Err(Error::KeyLength(17))
+        ));
+    }
+
+    #[test]
+    fn test_cbc_streaming_matches_one_shot() {
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let iv = vec![5u8; 16];
+        let data: Vec<u8> = (0..200u32).map(|x| (x * 7 % 251) as u8).collect();
+        for len in [0, 1, 15, 16, 17, 31, 32, 33, 48, 100, 200] {
+            let data = &data[..len];
+            let expected = cbc_encrypt(data, key, Some(&iv));
+            for step in [1, 5, 16, 64] {
+                let mut encryptor = CbcEncryptor::new(key, Some(&iv));
+                let mut encrypted = vec![];
+                for chunk in data.chunks(step) {
+                    encrypted.extend(encryptor.update(chunk));
+                }
+                encrypted.extend(encryptor.finalize());
+                assert_eq!(encrypted, expected);
+
+                let mut decryptor = CbcDecryptor::new(key, Some(&iv));
+                let mut decrypted = vec![];
+                for chunk in encrypted.chunks(step) {
+                    decrypted.extend(decryptor.update(chunk));
+                }
+                decrypted.extend(decryptor.finalize());
+                assert_eq!(decrypted, cbc_decrypt(&encrypted, key, Some(&iv)));
+                assert_eq!(decrypted, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cbc_reader_writer() {
+        use std::io::{Read, Write};
+
+        let key = "SASQUATCH JERSEY".as_bytes();
+        let data = "hello world hello world hello world".repeat(500);
+
+        let mut writer = CbcWriter::new(CbcEncryptor::new(key, None), vec![]);
+        for chunk in data.as_bytes().chunks(333) {
+            writer.write_all(chunk).unwrap();
+        }
+        let encrypted = writer.finish().unwrap();
+        assert_eq!(encrypted, cbc_encrypt(data.as_bytes(), key, None));
+
+        let mut reader = CbcReader::new(CbcDecryptor::new(key, None), &encrypted[..]);
+        let mut decrypted = String::new();
+        reader.read_to_string(&mut decrypted).unwrap();
+        assert_eq!(decrypted, data);
+
+        let mut reader = CbcReader::new(CbcDecryptor::new(key, None), &encrypted[..40]);
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_cbc_decrypt_only_unpads_last_block() {
+        // a block whose raw decryption happened to end in something that looks
+        // like padding used to lose those bytes
+        let key = "SASQUATCH JERSEY".as_bytes();
+        for i in 0..2000u32 {
+            let data: Vec<u8> = (0..47u32).map(|j| (i * 31 + j * 7) as u8).collect();
+            assert_eq!(cbc_decrypt(&cbc_encrypt(&data, key, None), key, None), data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use super::aes::{self, CtrKeystream, CtrLayout};
+    use super::mt19937::Mt19937;
+    use super::stream::*;
+    use rand::RngCore;
+
+    #[test]
+    fn test_mt19937_cipher() {
+        let plaintext = b"Come with me if you want to live";
+        let cyphertext = mt19937_encrypt(plaintext, 0xbeef);
+        assert_ne!(&cyphertext[..], &plaintext[..]);
+        assert_eq!(mt19937_decrypt(&cyphertext, 0xbeef), plaintext);
+        assert_ne!(mt19937_decrypt(&cyphertext, 0xbeee), plaintext);
+
+        let mut keystream = [0u8; 32];
+        Mt19937::new(0xbeef).fill_bytes(&mut keystream);
+        assert_eq!(mt19937_encrypt(&[0; 32], 0xbeef), keystream);
+    }
+
+    #[test]
+    fn test_stream_cipher_seek() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let ciphers: Vec<Box<dyn StreamCipher>> = vec![
+            Box::new(Mt19937Cipher::new(1234)),
+            Box::new(CtrKeystream::new(
+                key,
+                &[0; 8],
+                CtrLayout::Le64Nonce64Counter,
+            )),
+        ];
+        for mut cipher in ciphers {
+            let full = cipher.encrypt(&[0; 70]);
+            for offset in [0, 1, 3, 4, 5, 16, 33, 69] {
+                cipher.seek(offset);
+                assert_eq!(
+                    cipher.apply(&[0; 70])[..70 - offset as usize],
+                    full[offset as usize..]
+                );
+            }
+        }
+
+        let mut ctr = CtrKeystream::new(key, &[0; 8], CtrLayout::Le64Nonce64Counter);
+        assert_eq!(
+            StreamCipher::encrypt(&mut ctr, b"hello"),
+            aes::ctr_encrypt(b"hello", key, &[0; 8], CtrLayout::Le64Nonce64Counter)
+        );
+    }
 }
@@ -0,0 +1,174 @@
+// percent-escapes `%` and any of `specials`, enough to keep a value from
+// smuggling in its own separators
+pub fn escape(value: &str, specials: &[char]) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if c == '%' || specials.contains(&c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+// malformed escapes are left as they are rather than rejected
+pub fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut output = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                output.push(byte);
+                index += 3;
+            }
+            None => {
+                output.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&output).to_string()
+}
+
+pub fn encode_with(pairs: &[(&str, &str)], pair_sep: char, kv_sep: char) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            let key = escape(key, &[pair_sep, kv_sep]);
+            let value = escape(value, &[pair_sep, kv_sep]);
+            format!("{key}{kv_sep}{value}")
+        })
+        .collect::<Vec<String>>()
+        .join(&pair_sep.to_string())
+}
+
+// a segment without a separator becomes a key with an empty value
+pub fn parse_with(data: &str, pair_sep: char, kv_sep: char) -> Vec<(String, String)> {
+    data.split(pair_sep)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once(kv_sep) {
+            Some((key, value)) => (unescape(key), unescape(value)),
+            None => (unescape(pair), String::new()),
+        })
+        .collect()
+}
+
+pub fn encode(pairs: &[(&str, &str)]) -> String {
+    encode_with(pairs, '&', '=')
+}
+
+pub fn parse(data: &str) -> Vec<(String, String)> {
+    parse_with(data, '&', '=')
+}
+
+// escapes every byte outside the url unreserved set, for values that end
+// up in a request line
+pub fn url_escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for &byte in value.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    escaped
+}
+
+pub fn url_encode(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", url_escape(key), url_escape(value)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+pub fn get<'a>(pairs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+pub fn profile_for(email: &str) -> String {
+    encode(&[("email", email), ("uid", "10"), ("role", "user")])
+}
+
+// `;` separated cookie with quoted user input in the middle
+pub fn userdata_cookie(userdata: &str) -> String {
+    encode_with(
+        &[
+            ("comment1", "cooking MCs"),
+            ("userdata", userdata),
+            ("comment2", " like a pound of bacon"),
+        ],
+        ';',
+        '=',
+    )
+}
+
+pub fn is_admin(data: &str) -> bool {
+    get(&parse_with(data, ';', '='), "admin") == Some("true")
+}
+
+#[cfg(test)]
+mod cookie_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pairs = parse("foo=bar&baz=qux&zap=zazzle");
+
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(get(&pairs, "foo"), Some("bar"));
+        assert_eq!(get(&pairs, "zap"), Some("zazzle"));
+        assert_eq!(get(&pairs, "nope"), None);
+    }
+
+    #[test]
+    fn test_profile_for_escapes() {
+        let profile = profile_for("foo@bar.com&role=admin");
+
+        assert_eq!(profile, "email=foo@bar.com%26role%3Dadmin&uid=10&role=user");
+        let pairs = parse(&profile);
+        assert_eq!(get(&pairs, "email"), Some("foo@bar.com&role=admin"));
+        assert_eq!(get(&pairs, "role"), Some("user"));
+    }
+
+    #[test]
+    fn test_encode_parse_round_trip() {
+        let pairs = [("user;data", "a=b;c%d"), ("x", "")];
+        let encoded = encode_with(&pairs, ';', '=');
+
+        assert_eq!(encoded, "user%3Bdata=a%3Db%3Bc%25d;x=");
+        let parsed = parse_with(&encoded, ';', '=');
+        assert_eq!(parsed[0], ("user;data".to_string(), "a=b;c%d".to_string()));
+        assert_eq!(unescape("100%zz%4"), "100%zz%4");
+    }
+
+    #[test]
+    fn test_url_encode() {
+        let encoded = url_encode(&[("file", "my file/\u{e9}.txt"), ("sig", "a&b=c%~")]);
+
+        assert_eq!(encoded, "file=my%20file%2F%C3%A9.txt&sig=a%26b%3Dc%25~");
+        let parsed = parse(&encoded);
+        assert_eq!(get(&parsed, "file"), Some("my file/\u{e9}.txt"));
+        assert_eq!(get(&parsed, "sig"), Some("a&b=c%~"));
+    }
+
+    #[test]
+    fn test_userdata_cookie_quotes() {
+        let cookie = userdata_cookie(";admin=true;");
+
+        assert!(cookie.contains("userdata=%3Badmin%3Dtrue%3B;"));
+        assert!(!is_admin(&cookie));
+        assert!(is_admin("comment1=x;admin=true;comment2=y"));
+    }
+}
@@ -0,0 +1,118 @@
+use super::md4::Md4;
+use super::sha1::Sha1;
+use super::utils;
+use std::thread;
+use std::time::Duration;
+
+const HMAC_BLOCKSIZE: usize = 64;
+
+// SHA1(key || message), open to length extension
+pub fn secret_prefix_sha1(key: &[u8], msg: &[u8]) -> [u8; 20] {
+    let mut sha1 = Sha1::new();
+    sha1.update(key);
+    sha1.update(msg);
+    sha1.finalize()
+}
+
+pub fn verify_secret_prefix_sha1(key: &[u8], msg: &[u8], mac: &[u8]) -> bool {
+    utils::ct_eq(&secret_prefix_sha1(key, msg), mac)
+}
+
+pub fn secret_prefix_md4(key: &[u8], msg: &[u8]) -> [u8; 16] {
+    let mut md4 = Md4::new();
+    md4.update(key);
+    md4.update(msg);
+    md4.finalize()
+}
+
+pub fn verify_secret_prefix_md4(key: &[u8], msg: &[u8], mac: &[u8]) -> bool {
+    utils::ct_eq(&secret_prefix_md4(key, msg), mac)
+}
+
+pub fn hmac_sha1(key: &[u8], msg: &[u8]) -> [u8; 20] {
+    // long keys are hashed first, short ones zero padded to a block
+    let mut block = [0u8; HMAC_BLOCKSIZE];
+    if key.len() > HMAC_BLOCKSIZE {
+        block[..20].copy_from_slice(&Sha1::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha1::new();
+    inner.update(&block.map(|b| b ^ 0x36));
+    inner.update(msg);
+    let mut outer = Sha1::new();
+    outer.update(&block.map(|b| b ^ 0x5c));
+    outer.update(&inner.finalize());
+    outer.finalize()
+}
+
+// bails on the first mismatch and sleeps after every byte that matched,
+// so how long it takes says how much of the guess was right
+pub fn insecure_compare(a: &[u8], b: &[u8], delay: Duration) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return false;
+        }
+        thread::sleep(delay);
+    }
+    true
+}
+
+#[cfg(test)]
+mod mac_tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_prefix_sha1() {
+        let mac = secret_prefix_sha1(b"key", b"message");
+        assert_eq!(mac, Sha1::digest(b"keymessage"));
+        assert!(verify_secret_prefix_sha1(b"key", b"message", &mac));
+        assert!(!verify_secret_prefix_sha1(b"kex", b"message", &mac));
+        assert!(!verify_secret_prefix_sha1(b"key", b"message", &mac[..19]));
+    }
+
+    #[test]
+    fn test_secret_prefix_md4() {
+        let mac = secret_prefix_md4(b"key", b"message");
+        assert_eq!(mac, Md4::digest(b"keymessage"));
+        assert!(verify_secret_prefix_md4(b"key", b"message", &mac));
+        assert!(!verify_secret_prefix_md4(b"kex", b"message", &mac));
+    }
+
+    #[test]
+    fn test_hmac_sha1_rfc_2202() {
+        let vectors: [(&[u8], &[u8], &str); 3] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b617318655057264e28bc0b6fb378c8ef146be00",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            ),
+            (
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "aa4ae5e15272d00e95705637ce8a3b55ed402112",
+            ),
+        ];
+        for (key, msg, mac) in vectors {
+            assert_eq!(utils::bytes_to_hex(&hmac_sha1(key, msg)), mac);
+        }
+    }
+
+    #[test]
+    fn test_insecure_compare() {
+        let delay = Duration::ZERO;
+        assert!(insecure_compare(b"abc", b"abc", delay));
+        assert!(!insecure_compare(b"abc", b"abd", delay));
+        assert!(!insecure_compare(b"abc", b"ab", delay));
+        assert!(insecure_compare(b"", b"", delay));
+    }
+}
@@ -0,0 +1,148 @@
+// the Merkle-Damgård plumbing SHA-1 and MD4 share: block buffering, length
+// padding, and turning a digest back into a state. Each hash only brings its
+// compression function, initial state and byte order
+pub trait MdHash {
+    type State: Copy + std::fmt::Debug + AsRef<[u32]> + AsMut<[u32]>;
+    type Digest: Copy + Default + AsRef<[u8]> + AsMut<[u8]>;
+    const INITIAL_STATE: Self::State;
+    // byte order of the state words and of the bit length in the padding
+    const BIG_ENDIAN: bool;
+
+    fn compress(h: &mut Self::State, block: &[u8]);
+}
+
+pub const BLOCKSIZE: usize = 64;
+
+pub struct Hasher<H: MdHash> {
+    h: H::State,
+    buffer: Vec<u8>,
+    len: u64,
+}
+
+impl<H: MdHash> Hasher<H> {
+    pub fn new() -> Self {
+        Self::from_state(H::INITIAL_STATE, 0)
+    }
+
+    // picks up from a state after `processed_len` bytes, which has to be
+    // a whole number of blocks (padding included)
+    pub fn from_state(h: H::State, processed_len: u64) -> Self {
+        Hasher {
+            h,
+            buffer: Vec::with_capacity(BLOCKSIZE),
+            len: processed_len,
+        }
+    }
+
+    pub fn digest(data: &[u8]) -> H::Digest {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.len += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+        let full = self.buffer.len() - self.buffer.len() % BLOCKSIZE;
+        for block in self.buffer[..full].chunks(BLOCKSIZE) {
+            H::compress(&mut self.h, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    pub fn finalize(mut self) -> H::Digest {
+        let padding = padding::<H>(self.len);
+        let len = self.len;
+        self.update(&padding);
+        self.len = len;
+
+        let mut digest = H::Digest::default();
+        for (chunk, &word) in digest.as_mut().chunks_mut(4).zip(self.h.as_ref()) {
+            let bytes = if H::BIG_ENDIAN {
+                word.to_be_bytes()
+            } else {
+                word.to_le_bytes()
+            };
+            chunk.copy_from_slice(&bytes);
+        }
+        digest
+    }
+}
+
+impl<H: MdHash> Default for Hasher<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MdHash> Clone for Hasher<H> {
+    fn clone(&self) -> Self {
+        Hasher {
+            h: self.h,
+            buffer: self.buffer.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<H: MdHash> std::fmt::Debug for Hasher<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hasher")
+            .field("h", &self.h)
+            .field("buffer", &self.buffer)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+// the padding appended to a message of `len` bytes: 0x80, zeros up to
+// 56 mod 64, then the length in bits as a u64 in the hash's byte order
+pub fn padding<H: MdHash>(len: u64) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    let zeros = (BLOCKSIZE * 2 - 9 - (len as usize % BLOCKSIZE)) % BLOCKSIZE;
+    padding.extend(vec![0u8; zeros]);
+    let bits = len.wrapping_mul(8);
+    if H::BIG_ENDIAN {
+        padding.extend(bits.to_be_bytes());
+    } else {
+        padding.extend(bits.to_le_bytes());
+    }
+    padding
+}
+
+// a digest is the final state, ready to feed back into from_state
+pub fn digest_to_state<H: MdHash>(digest: &H::Digest) -> H::State {
+    let mut h = H::INITIAL_STATE;
+    for (word, chunk) in h.as_mut().iter_mut().zip(digest.as_ref().chunks(4)) {
+        let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        *word = if H::BIG_ENDIAN {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+    }
+    h
+}
+
+#[cfg(test)]
+mod md_tests {
+    use super::*;
+    use crate::common::md4::Md4Core;
+    use crate::common::sha1::Sha1Core;
+
+    #[test]
+    fn test_byte_order_follows_the_hash() {
+        // 3 bytes of message leave room for 0x80, 52 zeros and the length
+        let sha1 = padding::<Sha1Core>(3);
+        let md4 = padding::<Md4Core>(3);
+        assert_eq!(sha1[..53], md4[..53]);
+        assert_eq!(sha1[53..], 24u64.to_be_bytes());
+        assert_eq!(md4[53..], 24u64.to_le_bytes());
+
+        let mut glued = b"abc".to_vec();
+        glued.extend(md4);
+        let digest = Hasher::<Md4Core>::digest(b"abc");
+        let resumed = Hasher::<Md4Core>::from_state(digest_to_state::<Md4Core>(&digest), 64);
+        assert_eq!(resumed.finalize(), Hasher::<Md4Core>::digest(&glued));
+    }
+}
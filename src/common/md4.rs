@@ -0,0 +1,137 @@
+use super::md::{self, MdHash};
+
+const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+pub struct Md4Core;
+
+impl MdHash for Md4Core {
+    type State = [u32; 4];
+    type Digest = [u8; 16];
+    const INITIAL_STATE: [u32; 4] = INITIAL_STATE;
+    const BIG_ENDIAN: bool = false;
+
+    fn compress(h: &mut [u32; 4], block: &[u8]) {
+        compress(h, block);
+    }
+}
+
+pub type Md4 = md::Hasher<Md4Core>;
+
+// same as SHA-1's padding except the bit length is little-endian
+pub fn md_padding(len: u64) -> Vec<u8> {
+    md::padding::<Md4Core>(len)
+}
+
+pub fn digest_to_state(digest: &[u8; 16]) -> [u32; 4] {
+    md::digest_to_state::<Md4Core>(digest)
+}
+
+fn compress(h: &mut [u32; 4], block: &[u8]) {
+    let mut x = [0u32; 16];
+    for (word, chunk) in x.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+    let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+    let h_fn = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+    let [mut a, mut b, mut c, mut d] = *h;
+    // each round walks the words in its own order with a fixed set of shifts
+    for i in 0..16 {
+        let s = [3, 7, 11, 19][i % 4];
+        let t = a.wrapping_add(f(b, c, d)).wrapping_add(x[i]).rotate_left(s);
+        (a, b, c, d) = (d, t, b, c);
+    }
+    for i in 0..16 {
+        let k = (i % 4) * 4 + i / 4;
+        let s = [3, 5, 9, 13][i % 4];
+        let t = a
+            .wrapping_add(g(b, c, d))
+            .wrapping_add(x[k])
+            .wrapping_add(0x5a827999)
+            .rotate_left(s);
+        (a, b, c, d) = (d, t, b, c);
+    }
+    for i in 0..16 {
+        let k = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15][i];
+        let s = [3, 9, 11, 15][i % 4];
+        let t = a
+            .wrapping_add(h_fn(b, c, d))
+            .wrapping_add(x[k])
+            .wrapping_add(0x6ed9eba1)
+            .rotate_left(s);
+        (a, b, c, d) = (d, t, b, c);
+    }
+
+    for (word, value) in h.iter_mut().zip([a, b, c, d]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod md4_tests {
+    use super::*;
+    use crate::common::utils;
+
+    #[test]
+    fn test_rfc_1320_vectors() {
+        let vectors: [(&[u8], &str); 7] = [
+            (b"", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+            (b"a", "bde52cb31de33e46245e05fbdbd6fb24"),
+            (b"abc", "a448017aaf21d8525fc10ae87aa6729d"),
+            (b"message digest", "d9130a8164549fe818874806e1c7014b"),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                "d79e1c308aa5bbcdeea8ed63df412da9",
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "043f8582f241db351ce627e153e7f0e4",
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "e33b4ddc9c38f2199c3e7b164fcc0536",
+            ),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(utils::bytes_to_hex(&Md4::digest(message)), digest);
+        }
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let message: Vec<u8> = (0..200).collect();
+        for split in [0, 1, 55, 56, 63, 64, 65, 128, 200] {
+            let mut md4 = Md4::new();
+            md4.update(&message[..split]);
+            md4.update(&message[split..]);
+            assert_eq!(md4.finalize(), Md4::digest(&message));
+        }
+    }
+
+    #[test]
+    fn test_md_padding() {
+        for len in [0u64, 1, 55, 56, 63, 64, 119, 120] {
+            let padding = md_padding(len);
+            assert_eq!((len as usize + padding.len()) % 64, 0);
+            assert_eq!(padding[0], 0x80);
+            assert_eq!(padding[padding.len() - 8..], (len * 8).to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_from_state() {
+        let message = b"comment1=cooking%20MCs;userdata=foo";
+        let padding = md_padding(message.len() as u64);
+        let h = digest_to_state(&Md4::digest(message));
+
+        let mut resumed = Md4::from_state(h, (message.len() + padding.len()) as u64);
+        resumed.update(b";admin=true");
+
+        let mut glued = message.to_vec();
+        glued.extend(&padding);
+        glued.extend(b";admin=true");
+        assert_eq!(resumed.finalize(), Md4::digest(&glued));
+    }
+}
@@ -0,0 +1,682 @@
+use rand_core::{impls, Error, RngCore, SeedableRng};
+
+const N: usize = 624;
+const M: usize = 397;
+const MATRIX_A: u32 = 0x9908_b0df;
+const UPPER_MASK: u32 = 0x8000_0000;
+const LOWER_MASK: u32 = 0x7fff_ffff;
+
+pub const DEFAULT_SEED: u32 = 5489;
+
+#[derive(Clone)]
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    pub fn new(seed: u32) -> Self {
+        let mut state = [0u32; N];
+        state[0] = seed;
+        for i in 1..N {
+            let prev = state[i - 1];
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(prev ^ (prev >> 30))
+                .wrapping_add(i as u32);
+        }
+        Mt19937 { state, index: N }
+    }
+
+    // init_by_array from the reference implementation. An empty key has
+    // nothing to mix in, so it gets the default seed instead
+    pub fn from_array(key: &[u32]) -> Self {
+        if key.is_empty() {
+            return Self::default();
+        }
+        let mut mt = Self::new(19_650_218);
+        let state = &mut mt.state;
+        let (mut i, mut j) = (1, 0);
+        for _ in 0..N.max(key.len()) {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 30)).wrapping_mul(1_664_525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N - 1 {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 30)).wrapping_mul(1_566_083_941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+        }
+        state[0] = 0x8000_0000;
+        mt
+    }
+
+    // raw internal state, next output is tempered from state[index]
+    pub fn from_state(state: [u32; N], index: usize) -> Self {
+        Mt19937 {
+            state,
+            index: index.min(N),
+        }
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK) | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mag = if y & 1 == 1 { MATRIX_A } else { 0 };
+            self.state[i] = self.state[(i + M) % N] ^ (y >> 1) ^ mag;
+        }
+        self.index = 0;
+    }
+
+    pub fn temper(y: u32) -> u32 {
+        let mut y = y;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c_5680;
+        y ^= (y << 15) & 0xefc6_0000;
+        y ^= y >> 18;
+        y
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+        let y = self.state[self.index];
+        self.index += 1;
+        Self::temper(y)
+    }
+}
+
+impl Default for Mt19937 {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl RngCore for Mt19937 {
+    fn next_u32(&mut self) -> u32 {
+        Mt19937::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Mt19937 {
+    type Seed = [u8; 4];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u32::from_le_bytes(seed))
+    }
+}
+
+// inverts temper, each step is an xorshift we can undo a few bits at a time
+pub fn untemper(y: u32) -> u32 {
+    let mut y = y;
+    y ^= y >> 18;
+    y ^= (y << 15) & 0xefc6_0000;
+    y = undo_left_shift(y, 7, 0x9d2c_5680);
+    undo_right_shift(y, 11)
+}
+
+fn undo_left_shift(y: u32, shift: u32, mask: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ ((x << shift) & mask);
+    }
+    x
+}
+
+fn undo_right_shift(y: u32, shift: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ (x >> shift);
+    }
+    x
+}
+
+// the outputs must start right after a twist, i.e. the first output
+// of a fresh generator or every 624th after that
+pub fn clone_from_outputs(outputs: &[u32; N]) -> Mt19937 {
+    let mut state = [0u32; N];
+    for (word, &output) in state.iter_mut().zip(outputs.iter()) {
+        *word = untemper(output);
+    }
+    Mt19937::from_state(state, N)
+}
+
+// what we saw of one output: the bits set in `mask` are known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialOutput {
+    pub value: u32,
+    pub mask: u32,
+}
+
+impl PartialOutput {
+    pub fn full(value: u32) -> Self {
+        PartialOutput {
+            value,
+            mask: u32::MAX,
+        }
+    }
+
+    // an output that was consumed but not seen
+    pub fn skipped() -> Self {
+        PartialOutput { value: 0, mask: 0 }
+    }
+
+    // `value` is `(output >> shift)` cut down to its low `bits` bits
+    pub fn shifted(value: u32, shift: u32, bits: u32) -> Self {
+        let low_mask = if bits >= 32 {
+            u32::MAX
+        } else {
+            (1 << bits) - 1
+        };
+        PartialOutput {
+            value: (value & low_mask) << shift,
+            mask: low_mask << shift,
+        }
+    }
+
+    pub fn low_byte(value: u8) -> Self {
+        Self::shifted(value as u32, 0, 8)
+    }
+
+    pub fn high_byte(value: u8) -> Self {
+        Self::shifted(value as u32, 24, 8)
+    }
+}
+
+const STATE_BITS: usize = N * 32;
+const ROW_WORDS: usize = STATE_BITS / 64;
+
+// a state bit as a linear combination (over GF(2)) of the unknown state bits
+type Bits = Vec<u64>;
+
+fn xor_into(dest: &mut [u64], src: &[u64]) {
+    for (d, s) in dest.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+// tracks every state bit symbolically through the twists
+struct SymbolicState {
+    words: Vec<[Bits; 32]>,
+    index: usize,
+}
+
+impl SymbolicState {
+    fn new() -> Self {
+        let words = (0..N)
+            .map(|word| {
+                std::array::from_fn(|bit| {
+                    let var = word * 32 + bit;
+                    let mut bits = vec![0u64; ROW_WORDS];
+                    bits[var / 64] |= 1 << (var % 64);
+                    bits
+                })
+            })
+            .collect();
+        SymbolicState { words, index: 0 }
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            // y = upper bit of word i, lower 31 bits of the next word
+            let mut y: [Bits; 32] = std::array::from_fn(|bit| self.words[(i + 1) % N][bit].clone());
+            y[31] = self.words[i][31].clone();
+
+            let mut new: [Bits; 32] =
+                std::array::from_fn(|bit| self.words[(i + M) % N][bit].clone());
+            for bit in 0..31 {
+                xor_into(&mut new[bit], &y[bit + 1]);
+            }
+            for (bit, word) in new.iter_mut().enumerate() {
+                if MATRIX_A >> bit & 1 == 1 {
+                    xor_into(word, &y[0]);
+                }
+            }
+            self.words[i] = new;
+        }
+        self.index = 0;
+    }
+
+    fn next_word(&mut self) -> &[Bits; 32] {
+        if self.index >= N {
+            self.twist();
+        }
+        self.index += 1;
+        &self.words[self.index - 1]
+    }
+}
+
+// incremental gaussian elimination, each row's lowest set bit is its pivot
+struct Echelon {
+    pivots: Vec<Option<usize>>,
+    rows: Vec<(Bits, bool)>,
+}
+
+impl Echelon {
+    fn new() -> Self {
+        Echelon {
+            pivots: vec![None; STATE_BITS],
+            rows: vec![],
+        }
+    }
+
+    // false if the equation contradicts what we already have
+    fn insert(&mut self, mut row: Bits, mut rhs: bool) -> bool {
+        let mut start = 0;
+        while let Some(offset) = row[start..].iter().position(|&w| w != 0) {
+            start += offset;
+            let col = start * 64 + row[start].trailing_zeros() as usize;
+            match self.pivots[col] {
+                Some(pivot) => {
+                    let (pivot_row, pivot_rhs) = &self.rows[pivot];
+                    xor_into(&mut row[start..], &pivot_row[start..]);
+                    rhs ^= pivot_rhs;
+                }
+                None => {
+                    self.pivots[col] = Some(self.rows.len());
+                    self.rows.push((row, rhs));
+                    return true;
+                }
+            }
+        }
+        !rhs
+    }
+
+    // unconstrained bits come out as zero
+    fn solve(&self) -> Bits {
+        let mut solution = vec![0u64; ROW_WORDS];
+        for col in (0..STATE_BITS).rev() {
+            if let Some(pivot) = self.pivots[col] {
+                let (row, rhs) = &self.rows[pivot];
+                let parity = row
+                    .iter()
+                    .zip(&solution)
+                    .map(|(r, s)| (r & s).count_ones())
+                    .sum::<u32>();
+                if (parity % 2 == 1) != *rhs {
+                    solution[col / 64] |= 1 << (col % 64);
+                }
+            }
+        }
+        solution
+    }
+}
+
+// rebuilds the generator from outputs we only partly saw (bytes, shifted
+// ranges, skipped values) by solving for the state bits as a linear system.
+// The first output must follow a twist, and enough bits have to leak
+// overall: at least 19937, more in practice. Returns the generator
+// positioned after the last output, or None if the outputs are inconsistent
+pub fn clone_from_partial_outputs(outputs: &[PartialOutput]) -> Option<Mt19937> {
+    // which state bits feed each tempered output bit
+    let temper_columns: Vec<u32> = (0..32).map(|bit| Mt19937::temper(1 << bit)).collect();
+
+    let mut symbolic = SymbolicState::new();
+    let mut echelon = Echelon::new();
+    for output in outputs {
+        let word = symbolic.next_word();
+        for out_bit in (0..32).filter(|b| output.mask >> b & 1 == 1) {
+            let mut row = vec![0u64; ROW_WORDS];
+            for (in_bit, column) in temper_columns.iter().enumerate() {
+                if column >> out_bit & 1 == 1 {
+                    xor_into(&mut row, &word[in_bit]);
+                }
+            }
+            if !echelon.insert(row, output.value >> out_bit & 1 == 1) {
+                return None;
+            }
+        }
+    }
+
+    let solution = echelon.solve();
+    let mut state = [0u32; N];
+    for (i, word) in state.iter_mut().enumerate() {
+        *word = (solution[i / 2] >> (32 * (i % 2))) as u32;
+    }
+    let mut mt = Mt19937::from_state(state, 0);
+    for _ in outputs {
+        mt.next_u32();
+    }
+    Some(mt)
+}
+
+const N64: usize = 312;
+const M64: usize = 156;
+const MATRIX_A64: u64 = 0xb502_6f5a_a966_19e9;
+const UPPER_MASK64: u64 = 0xffff_ffff_8000_0000;
+const LOWER_MASK64: u64 = 0x7fff_ffff;
+
+pub const DEFAULT_SEED64: u64 = 5489;
+
+#[derive(Clone)]
+pub struct Mt19937_64 {
+    state: [u64; N64],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    pub fn new(seed: u64) -> Self {
+        let mut state = [0u64; N64];
+        state[0] = seed;
+        for i in 1..N64 {
+            let prev = state[i - 1];
+            state[i] = 6_364_136_223_846_793_005u64
+                .wrapping_mul(prev ^ (prev >> 62))
+                .wrapping_add(i as u64);
+        }
+        Mt19937_64 { state, index: N64 }
+    }
+
+    // init_by_array64 from the reference implementation, an empty key gets
+    // the default seed
+    pub fn from_array(key: &[u64]) -> Self {
+        if key.is_empty() {
+            return Self::default();
+        }
+        let mut mt = Self::new(19_650_218);
+        let state = &mut mt.state;
+        let (mut i, mut j) = (1, 0);
+        for _ in 0..N64.max(key.len()) {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 62)).wrapping_mul(3_935_559_000_370_003_845))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u64);
+            i += 1;
+            j += 1;
+            if i >= N64 {
+                state[0] = state[N64 - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N64 - 1 {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 62)).wrapping_mul(2_862_933_555_777_941_757))
+                .wrapping_sub(i as u64);
+            i += 1;
+            if i >= N64 {
+                state[0] = state[N64 - 1];
+                i = 1;
+            }
+        }
+        state[0] = 1 << 63;
+        mt
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N64 {
+            let y = (self.state[i] & UPPER_MASK64) | (self.state[(i + 1) % N64] & LOWER_MASK64);
+            let mag = if y & 1 == 1 { MATRIX_A64 } else { 0 };
+            self.state[i] = self.state[(i + M64) % N64] ^ (y >> 1) ^ mag;
+        }
+        self.index = 0;
+    }
+
+    pub fn temper(y: u64) -> u64 {
+        let mut y = y;
+        y ^= (y >> 29) & 0x5555_5555_5555_5555;
+        y ^= (y << 17) & 0x71d6_7fff_eda6_0000;
+        y ^= (y << 37) & 0xfff7_eee0_0000_0000;
+        y ^= y >> 43;
+        y
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        if self.index >= N64 {
+            self.twist();
+        }
+        let y = self.state[self.index];
+        self.index += 1;
+        Self::temper(y)
+    }
+}
+
+impl Default for Mt19937_64 {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED64)
+    }
+}
+
+impl RngCore for Mt19937_64 {
+    // upper half, same as taking a u32 from a 64-bit engine elsewhere
+    fn next_u32(&mut self) -> u32 {
+        (Mt19937_64::next_u64(self) >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Mt19937_64::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Mt19937_64 {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u64::from_le_bytes(seed))
+    }
+}
+
+#[cfg(test)]
+mod mt19937_tests {
+    use super::*;
+
+    #[test]
+    fn test_default_seed() {
+        let mut mt = Mt19937::default();
+        assert_eq!(mt.next_u32(), 3_499_211_612);
+
+        // the C++ standard pins the 10000th output of a default constructed engine
+        let mut mt = Mt19937::default();
+        let output = (0..10_000).map(|_| mt.next_u32()).last().unwrap();
+        assert_eq!(output, 4_123_659_995);
+    }
+
+    #[test]
+    fn test_init_by_array() {
+        // first outputs of mt19937ar.out
+        let mut mt = Mt19937::from_array(&[0x123, 0x234, 0x345, 0x456]);
+        let outputs: Vec<u32> = (0..5).map(|_| mt.next_u32()).collect();
+        assert_eq!(
+            outputs,
+            [
+                1_067_595_299,
+                955_945_823,
+                477_289_528,
+                4_107_218_783,
+                4_228_976_476
+            ]
+        );
+    }
+
+    #[test]
+    fn test_init_by_empty_array() {
+        let mut mt = Mt19937::from_array(&[]);
+        let mut expected = Mt19937::default();
+        for _ in 0..5 {
+            assert_eq!(mt.next_u32(), expected.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_64_default_seed() {
+        let mut mt = Mt19937_64::default();
+        assert_eq!(mt.next_u64(), 14_514_284_786_278_117_030);
+
+        let mut mt = Mt19937_64::default();
+        let output = (0..10_000).map(|_| mt.next_u64()).last().unwrap();
+        assert_eq!(output, 9_981_545_732_273_789_042);
+    }
+
+    #[test]
+    fn test_64_init_by_array() {
+        // first outputs of mt19937-64.out
+        let mut mt = Mt19937_64::from_array(&[0x12345, 0x23456, 0x34567, 0x45678]);
+        let outputs: Vec<u64> = (0..5).map(|_| mt.next_u64()).collect();
+        assert_eq!(
+            outputs,
+            [
+                7_266_447_313_870_364_031,
+                4_946_485_549_665_804_864,
+                16_945_909_448_695_747_420,
+                16_394_063_075_524_226_720,
+                4_873_882_236_456_199_058
+            ]
+        );
+    }
+
+    #[test]
+    fn test_64_init_by_empty_array() {
+        let mut mt = Mt19937_64::from_array(&[]);
+        let mut expected = Mt19937_64::default();
+        for _ in 0..5 {
+            assert_eq!(mt.next_u64(), expected.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_rng_core() {
+        let mut a = Mt19937::new(1234);
+        let mut b = Mt19937::from_seed(1234u32.to_le_bytes());
+        let mut bytes = [0u8; 8];
+        RngCore::fill_bytes(&mut a, &mut bytes);
+
+        assert_eq!(bytes[..4], b.next_u32().to_le_bytes());
+        assert_eq!(bytes[4..], b.next_u32().to_le_bytes());
+    }
+
+    #[test]
+    fn test_seeded_helpers_and_oracles() {
+        use crate::common::attacks::ecb_cut_and_paste::ProfileOracle;
+        use crate::common::{aes, utils};
+
+        let a = utils::generate_random_string_with(&mut Mt19937::new(42), 32);
+        let b = utils::generate_random_string_with(&mut Mt19937::new(42), 32);
+        assert_eq!(a, b);
+        assert_eq!(
+            aes::random_aes_key_with(&mut Mt19937_64::new(7))
+                .expose()
+                .len(),
+            16
+        );
+
+        let first = ProfileOracle::with_rng(&mut Mt19937::new(1));
+        let second = ProfileOracle::with_rng(&mut Mt19937::new(1));
+        assert_eq!(
+            first.encrypt_profile("foo@bar.com"),
+            second.encrypt_profile("foo@bar.com")
+        );
+    }
+
+    #[test]
+    fn test_untemper() {
+        for y in [0, 1, 0xdead_beef, u32::MAX, 3_499_211_612] {
+            assert_eq!(untemper(Mt19937::temper(y)), y);
+        }
+    }
+
+    #[test]
+    fn test_clone_from_outputs() {
+        let mut mt = Mt19937::new(0xc0ffee);
+        let mut outputs = [0u32; 624];
+        for output in outputs.iter_mut() {
+            *output = mt.next_u32();
+        }
+
+        let mut clone = clone_from_outputs(&outputs);
+        for _ in 0..2000 {
+            assert_eq!(clone.next_u32(), mt.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_clone_from_partial_outputs_shifted() {
+        // only the top 31 bits of each output leak, e.g. a value in 0..2^31
+        let mut mt = Mt19937::new(31337);
+        let outputs: Vec<PartialOutput> = (0..1400)
+            .map(|_| PartialOutput::shifted(mt.next_u32() >> 1, 1, 31))
+            .collect();
+
+        let mut clone = clone_from_partial_outputs(&outputs).unwrap();
+        for _ in 0..1000 {
+            assert_eq!(clone.next_u32(), mt.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_clone_from_partial_outputs_low_byte() {
+        // only the low byte of each output leaks. The low bits mix slowly through
+        // the twist, so it takes well over the 2500 outputs the bit count suggests
+        let mut mt = Mt19937::new(2024);
+        let outputs: Vec<PartialOutput> = (0..4000)
+            .map(|_| PartialOutput::low_byte(mt.next_u32() as u8))
+            .collect();
+
+        let mut clone = clone_from_partial_outputs(&outputs).unwrap();
+        for _ in 0..1000 {
+            assert_eq!(clone.next_u32(), mt.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_clone_from_partial_outputs_high_byte() {
+        let mut mt = Mt19937::new(7);
+        let outputs: Vec<PartialOutput> = (0..3000)
+            .map(|_| PartialOutput::high_byte((mt.next_u32() >> 24) as u8))
+            .collect();
+
+        let mut clone = clone_from_partial_outputs(&outputs).unwrap();
+        for _ in 0..1000 {
+            assert_eq!(clone.next_u32(), mt.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_clone_from_partial_outputs_inconsistent() {
+        // once the state is pinned down, a wrong output has to be rejected
+        let mut mt = Mt19937::new(5);
+        let mut outputs: Vec<PartialOutput> = (0..624)
+            .map(|_| PartialOutput::full(mt.next_u32()))
+            .collect();
+        outputs.push(PartialOutput::full(mt.next_u32() ^ 1));
+        assert!(clone_from_partial_outputs(&outputs).is_none());
+    }
+}
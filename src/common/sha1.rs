@@ -0,0 +1,135 @@
+use super::md::{self, MdHash};
+
+const INITIAL_STATE: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+pub struct Sha1Core;
+
+impl MdHash for Sha1Core {
+    type State = [u32; 5];
+    type Digest = [u8; 20];
+    const INITIAL_STATE: [u32; 5] = INITIAL_STATE;
+    const BIG_ENDIAN: bool = true;
+
+    fn compress(h: &mut [u32; 5], block: &[u8]) {
+        compress(h, block);
+    }
+}
+
+pub type Sha1 = md::Hasher<Sha1Core>;
+
+// the padding appended to a message of `len` bytes: 0x80, zeros up to
+// 56 mod 64, then the length in bits as a big-endian u64
+pub fn md_padding(len: u64) -> Vec<u8> {
+    md::padding::<Sha1Core>(len)
+}
+
+pub fn digest_to_state(digest: &[u8; 20]) -> [u32; 5] {
+    md::digest_to_state::<Sha1Core>(digest)
+}
+
+fn compress(h: &mut [u32; 5], block: &[u8]) {
+    let mut w = [0u32; 80];
+    for (word, chunk) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *h;
+    for (i, word) in w.iter().enumerate() {
+        let (f, k) = match i {
+            0..=19 => ((b & c) | (!b & d), 0x5a827999),
+            20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+
+    for (word, value) in h.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod sha1_tests {
+    use super::*;
+    use crate::common::utils;
+
+    #[test]
+    fn test_fips_180_vectors() {
+        let vectors: [(&[u8], &str); 3] = [
+            (b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            ),
+            (b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(utils::bytes_to_hex(&Sha1::digest(message)), digest);
+        }
+
+        let mut sha1 = Sha1::new();
+        for _ in 0..1000 {
+            sha1.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            utils::bytes_to_hex(&sha1.finalize()),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let message: Vec<u8> = (0..200).collect();
+        for split in [0, 1, 55, 56, 63, 64, 65, 128, 200] {
+            let mut sha1 = Sha1::new();
+            sha1.update(&message[..split]);
+            sha1.update(&message[split..]);
+            assert_eq!(sha1.finalize(), Sha1::digest(&message));
+        }
+    }
+
+    #[test]
+    fn test_md_padding() {
+        for len in [0u64, 1, 55, 56, 63, 64, 119, 120] {
+            let padding = md_padding(len);
+            assert_eq!((len as usize + padding.len()) % 64, 0);
+            assert_eq!(padding[0], 0x80);
+            assert_eq!(padding[padding.len() - 8..], (len * 8).to_be_bytes());
+        }
+        assert_eq!(md_padding(55).len(), 9);
+        assert_eq!(md_padding(56).len(), 72);
+    }
+
+    #[test]
+    fn test_from_state() {
+        let message = b"comment1=cooking%20MCs;userdata=foo";
+        let padding = md_padding(message.len() as u64);
+        let h = digest_to_state(&Sha1::digest(message));
+
+        let mut resumed = Sha1::from_state(h, (message.len() + padding.len()) as u64);
+        resumed.update(b";admin=true");
+
+        let mut glued = message.to_vec();
+        glued.extend(&padding);
+        glued.extend(b";admin=true");
+        assert_eq!(resumed.finalize(), Sha1::digest(&glued));
+        assert_eq!(
+            Sha1::from_state(INITIAL_STATE, 0).finalize(),
+            Sha1::digest(b"")
+        );
+    }
+}